@@ -0,0 +1,135 @@
+//! Compares checking every mover against every collider, like `apply_movement` and
+//! `move_projectiles` used to, with asking the `SpatialHash` for what's nearby, as coins
+//! pile up on the map.
+//!
+//! Run with `cargo bench --bench spatial_hash`.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use chexy_butt_balloons::demo::{
+    lib::RoomId,
+    physics::{check_collision, Collider, CollisionLayers, SpatialHash},
+};
+
+/// A full room's worth of ducks, and the projectiles they have in the air.
+const MOVERS: usize = 24;
+const FRAMES: u32 = 1000;
+const ROOM: Option<RoomId> = Some(RoomId(0));
+
+struct Body {
+    entity: Entity,
+    position: Vec3,
+    collider: Collider,
+}
+
+fn body(rng: &mut fastrand::Rng, index: usize, size: Vec2) -> Body {
+    Body {
+        entity: Entity::from_raw(index as u32),
+        position: Vec3::new(rng.f32() * 1600. - 800., rng.f32() * 1120. - 560., 0.),
+        collider: Collider::aabb(size, CollisionLayers::ALL, CollisionLayers::ALL),
+    }
+}
+
+/// Coins scattered over the arena, and movers among them.
+fn world(coins: usize) -> (Vec<Body>, Vec<Body>) {
+    let mut rng = fastrand::Rng::with_seed(7);
+    let coins = (0..coins)
+        .map(|index| body(&mut rng, index, Vec2::new(20., 24.)))
+        .collect();
+    let movers = (0..MOVERS)
+        .map(|index| body(&mut rng, 1_000_000 + index, Vec2::new(14., 24.)))
+        .collect();
+    (coins, movers)
+}
+
+/// Move every mover a little, back and forth, so they have to be updated every frame.
+fn step(movers: &mut [Body], frame: u32) {
+    let offset = if frame % 2 == 0 { 5. } else { -5. };
+    for mover in movers {
+        mover.position.x += offset;
+    }
+}
+
+/// Like `apply_movement`, every mover tries a step along each axis against every collider.
+fn hits(mover: &Body, other: &Body) -> usize {
+    let step_x = mover.position + Vec3::X * 5.;
+    let step_y = mover.position + Vec3::Y * 5.;
+    check_collision(&step_x, &mover.collider, &other.position, &other.collider) as usize
+        + check_collision(&step_y, &mover.collider, &other.position, &other.collider) as usize
+}
+
+fn brute_force(coins: &[Body], movers: &[Body]) -> usize {
+    movers
+        .iter()
+        .flat_map(|mover| coins.iter().map(move |coin| hits(mover, coin)))
+        .sum()
+}
+
+fn spatial_hash(spatial_hash: &mut SpatialHash, coins: &[Body], movers: &[Body]) -> usize {
+    // Coins never move, so only the movers need updating, like `update_spatial_hash` does.
+    for mover in movers {
+        spatial_hash.insert(
+            mover.entity,
+            ROOM,
+            mover.position.xy(),
+            mover.collider.size(),
+        );
+    }
+    movers
+        .iter()
+        .flat_map(|mover| {
+            spatial_hash
+                .query(ROOM, mover.position.xy(), mover.collider.size() + 10.)
+                .into_iter()
+                .filter_map(|entity| coins.get(entity.index() as usize))
+                .map(move |coin| hits(mover, coin))
+        })
+        .sum()
+}
+
+fn time(mut frame: impl FnMut(u32) -> usize) -> Duration {
+    let start = Instant::now();
+    for index in 0..FRAMES {
+        black_box(frame(index));
+    }
+    start.elapsed() / FRAMES
+}
+
+fn main() {
+    println!(
+        "{:>6} {:>14} {:>14} {:>8}",
+        "coins", "brute force", "spatial hash", "speedup"
+    );
+    for coins in [100, 300, 500, 1000, 3000] {
+        let (coins_bodies, mut movers) = world(coins);
+        let mut hash = SpatialHash::default();
+        for coin in &coins_bodies {
+            hash.insert(coin.entity, ROOM, coin.position.xy(), coin.collider.size());
+        }
+        assert_eq!(
+            brute_force(&coins_bodies, &movers),
+            spatial_hash(&mut hash, &coins_bodies, &movers),
+            "both should find the same collisions"
+        );
+
+        let brute = time(|frame| {
+            step(&mut movers, frame);
+            brute_force(&coins_bodies, &movers)
+        });
+        let hashed = time(|frame| {
+            step(&mut movers, frame);
+            spatial_hash(&mut hash, &coins_bodies, &movers)
+        });
+        println!(
+            "{:>6} {:>14?} {:>14?} {:>7.1}x",
+            coins,
+            brute,
+            hashed,
+            brute.as_secs_f64() / hashed.as_secs_f64()
+        );
+    }
+}
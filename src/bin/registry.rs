@@ -0,0 +1,58 @@
+//! A small registry that dedicated servers register with and heartbeat to,
+//! so clients can find them from the "Browse Servers" screen.
+//!
+//! Run it locally with `cargo run --bin registry`, optionally passing `--addr <ip:port>`.
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use chexy_butt_balloons::demo::registry::{ServerInfo, DEFAULT_REGISTRY_ADDR, LISTING_TIMEOUT};
+use warp::Filter;
+
+/// Registered servers by their game address, along with when we last heard from them.
+type Listings = Arc<Mutex<HashMap<SocketAddr, (ServerInfo, Instant)>>>;
+
+#[tokio::main]
+async fn main() {
+    let addr: SocketAddr = std::env::args()
+        .skip_while(|arg| arg != "--addr")
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_REGISTRY_ADDR.to_string())
+        .parse()
+        .expect("--addr should be an <ip:port> address");
+
+    let listings = Listings::default();
+    let with_listings = warp::any().map(move || listings.clone());
+
+    // Registering again is how servers send their heartbeat.
+    let register = warp::post()
+        .and(warp::path!("register"))
+        .and(warp::body::json())
+        .and(with_listings.clone())
+        .map(|info: ServerInfo, listings: Listings| {
+            let mut listings = listings.lock().unwrap();
+            if !listings.contains_key(&info.native_addr) {
+                println!("Server {} registered at {}", info.name, info.native_addr);
+            }
+            listings.insert(info.native_addr, (info, Instant::now()));
+            warp::reply()
+        });
+
+    let servers = warp::get()
+        .and(warp::path!("servers"))
+        .and(with_listings)
+        .map(|listings: Listings| {
+            let mut listings = listings.lock().unwrap();
+            listings.retain(|_, (_, last_seen)| last_seen.elapsed() < LISTING_TIMEOUT);
+            let servers: Vec<ServerInfo> = listings.values().map(|(info, _)| info.clone()).collect();
+            warp::reply::json(&servers)
+        });
+
+    let cors = warp::cors().allow_any_origin();
+    println!("Registry listening on {}", addr);
+    warp::serve(register.or(servers).with(cors)).run(addr).await;
+}
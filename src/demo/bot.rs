@@ -0,0 +1,427 @@
+//! Server-side bot AI.
+//!
+//! Every time it reacts, each bot scores a handful of behaviours (seek coins, bank them,
+//! evade, chase) and acts on the most useful one. Bots steer by writing their [`PlayerInput`]
+//! and shoot and dash through [`AttackRequest`] and [`DashRequest`], exactly like human
+//! players, so they obey the same movement, collision and attack rules.
+//!
+//! How quickly they react, how well they aim and what they care about comes from the
+//! difficulties and personalities in `assets/bots.ron`.
+
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    dash::DashRequest,
+    lib::{BotConfig, Player, PlayerInput, RoomId, Velocity},
+    nav::NavGrid,
+    nest::{Nest, NEST_RADIUS},
+    player::Coin,
+    server::{AttackRequest, Projectile, ServerLobby},
+    weapon::Weapon,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<BotProfiles>();
+    app.add_systems(Update, bot_think);
+}
+
+/// Bots with at least this many coins have something to lose, and run from other ducks.
+const LARGE_SCORE: i64 = 10;
+/// Other ducks closer than this are a threat to a large bot.
+const THREAT_RADIUS: f32 = 250.0;
+/// How far ahead bots look for projectiles coming their way.
+const DANGER_DISTANCE: f32 = 300.0;
+/// Projectiles passing closer than this are worth dodging.
+const DANGER_WIDTH: f32 = 40.0;
+/// Bots evading anything more threatening than this dash away.
+const DASH_THREAT: f32 = 0.6;
+/// Below this, a direction component doesn't count as pressing that key.
+const INPUT_DEADZONE: f32 = 0.38;
+
+/// Paths are recomputed at least this often, as the world keeps moving.
+const REPATH_SECS: f32 = 0.5;
+/// A target that moved further than this since the last path gets a new one straight away.
+const REPATH_DISTANCE: f32 = 60.0;
+
+/// How well a bot plays.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Difficulty {
+    pub name: String,
+    /// Seconds between two decisions. The bot keeps doing what it decided in between.
+    pub reaction_secs: f32,
+    /// Shots go up to this many degrees either side of where the bot aimed.
+    pub aim_error_degrees: f32,
+    pub fire_interval_secs: f32,
+    /// Whether shots aim where a moving duck is going to be, rather than where it is.
+    pub leads_shots: bool,
+    /// How far the bot notices coins and other ducks.
+    pub sight_radius: f32,
+    pub dodges_projectiles: bool,
+}
+
+/// What a bot cares about. Each weight scales how much it wants to do one thing.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Personality {
+    pub name: String,
+    /// Collecting coins.
+    pub greed: f32,
+    /// Getting out of harm's way.
+    pub caution: f32,
+    /// Going after other ducks.
+    pub aggression: f32,
+}
+
+/// Every difficulty and personality bots can be given, loaded from `assets/bots.ron`.
+#[derive(Debug, Clone, Deserialize, Resource)]
+pub struct BotProfiles {
+    /// What new bots play with unless the host picks something else.
+    pub default: BotConfig,
+    pub difficulties: Vec<Difficulty>,
+    pub personalities: Vec<Personality>,
+}
+
+impl Default for BotProfiles {
+    fn default() -> Self {
+        // Embedded so the web build and offline practice don't have to load it.
+        ron::from_str(include_str!("../../assets/bots.ron")).expect("bots.ron should be valid")
+    }
+}
+
+impl BotProfiles {
+    pub fn difficulty(&self, name: &str) -> Option<&Difficulty> {
+        self.difficulties
+            .iter()
+            .find(|difficulty| difficulty.name == name)
+    }
+
+    pub fn personality(&self, name: &str) -> Option<&Personality> {
+        self.personalities
+            .iter()
+            .find(|personality| personality.name == name)
+    }
+
+    /// A bot playing with `config`, or `None` if it names a profile that doesn't exist.
+    pub fn bot(&self, config: &BotConfig) -> Option<Bot> {
+        Some(Bot::new(
+            self.difficulty(&config.difficulty)?.clone(),
+            self.personality(&config.personality)?.clone(),
+        ))
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct Bot {
+    pub difficulty: Difficulty,
+    pub personality: Personality,
+    pub fire_cooldown: Timer,
+    /// Waypoints left on the way to the current target.
+    pub path: Vec<Vec2>,
+    path_target: Vec2,
+    repath: Timer,
+    reaction: Timer,
+    behaviour: Behaviour,
+}
+
+impl Bot {
+    pub fn new(difficulty: Difficulty, personality: Personality) -> Self {
+        Self {
+            fire_cooldown: Timer::from_seconds(difficulty.fire_interval_secs, TimerMode::Once),
+            // Finishes on the first tick, so the bot makes up its mind straight away.
+            reaction: Timer::from_seconds(0.0, TimerMode::Once),
+            difficulty,
+            personality,
+            path: Vec::new(),
+            path_target: Vec2::ZERO,
+            repath: Timer::from_seconds(REPATH_SECS, TimerMode::Once),
+            behaviour: Behaviour::Idle,
+        }
+    }
+
+    pub fn config(&self) -> BotConfig {
+        BotConfig {
+            difficulty: self.difficulty.name.clone(),
+            personality: self.personality.name.clone(),
+        }
+    }
+
+    /// The direction to walk in to reach `target`, routing around obstacles on the grid.
+    fn steer_towards(&mut self, grid: Option<&NavGrid>, position: Vec2, target: Vec2) -> Vec2 {
+        let Some(grid) = grid else {
+            return (target - position).normalize_or_zero();
+        };
+        if self.repath.finished() || self.path_target.distance(target) > REPATH_DISTANCE {
+            // If there's no way through, walking straight at it is the best we can do.
+            self.path = grid
+                .find_path(position, target)
+                .unwrap_or_else(|| vec![target]);
+            self.path_target = target;
+            self.repath.reset();
+        }
+        while self.path.len() > 1 && self.path[0].distance(position) < grid.cell_size() {
+            self.path.remove(0);
+        }
+        self.path.first().map_or(Vec2::ZERO, |waypoint| {
+            (*waypoint - position).normalize_or_zero()
+        })
+    }
+}
+
+/// What a bot decided to do the last time it reacted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Behaviour {
+    /// Walk to the coin at this position.
+    SeekCoin(Vec2),
+    /// Get away, in this direction.
+    Evade(Vec2),
+    /// Close in on the duck at this position.
+    Chase(Vec2),
+    /// Sit in the nest at this position until the coins are banked.
+    Bank(Vec2),
+    Idle,
+}
+
+#[allow(clippy::type_complexity)]
+fn bot_think(
+    time: Res<Time>,
+    lobby: Res<ServerLobby>,
+    mut bots: Query<(
+        Entity,
+        &Transform,
+        &Player,
+        &RoomId,
+        &mut Bot,
+        &mut PlayerInput,
+        &Weapon,
+    )>,
+    ducks: Query<(Entity, &Transform, &RoomId, Option<&Velocity>), With<Player>>,
+    coins: Query<(&Transform, &Coin, &RoomId)>,
+    nests: Query<(&Transform, &Nest)>,
+    projectiles: Query<(&Transform, &Projectile, &RoomId)>,
+    mut attacks: EventWriter<AttackRequest>,
+    mut dashes: EventWriter<DashRequest>,
+) {
+    for (entity, transform, player, room_id, mut bot, mut input, weapon) in &mut bots {
+        bot.fire_cooldown.tick(time.delta());
+        bot.repath.tick(time.delta());
+        bot.reaction.tick(time.delta());
+        let Some(room) = lobby.rooms.get(room_id).filter(|room| room.in_game()) else {
+            *input = PlayerInput::default();
+            continue;
+        };
+        let position = transform.translation.xy();
+        let sight_radius = bot.difficulty.sight_radius;
+
+        // Rarer coins are worth walking further for.
+        let nearest_coin = coins
+            .iter()
+            .filter(|(_, _, coin_room)| *coin_room == room_id)
+            .map(|(transform, coin, _)| {
+                let coin_position = transform.translation.xy();
+                let cost = coin_position.distance(position) / coin.kind.value() as f32;
+                (coin_position, cost)
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(coin, _)| coin);
+
+        let nearest_enemy = ducks
+            .iter()
+            .filter(|(other, _, other_room, _)| *other != entity && *other_room == room_id)
+            .map(|(_, other, _, velocity)| {
+                (
+                    other.translation.xy(),
+                    velocity.map_or(Vec2::ZERO, |velocity| velocity.0),
+                )
+            })
+            .min_by(|(a, _), (b, _)| a.distance(position).total_cmp(&b.distance(position)));
+
+        let mut dash = false;
+        if bot.reaction.finished() {
+            let personality = &bot.personality;
+            let mut options = vec![Behaviour::Idle];
+            let mut utilities = vec![0.05];
+
+            if let Some(coin) = nearest_coin.filter(|coin| coin.distance(position) < sight_radius) {
+                options.push(Behaviour::SeekCoin(coin));
+                utilities.push(personality.greed * (1.2 - coin.distance(position) / sight_radius));
+            }
+
+            let (threat, away) = threat(
+                position,
+                entity,
+                player.score >= LARGE_SCORE,
+                nearest_enemy.map(|(enemy, _)| enemy),
+                projectiles
+                    .iter()
+                    .filter(|(_, _, projectile_room)| {
+                        bot.difficulty.dodges_projectiles && *projectile_room == room_id
+                    })
+                    .map(|(transform, projectile, _)| (transform.translation.xy(), projectile)),
+            );
+            if threat > 0.0 {
+                options.push(Behaviour::Evade(away));
+                utilities.push(personality.caution * 1.5 * threat);
+            }
+
+            // The more a bot carries, the more it wants to put it somewhere safe.
+            if let Some((nest, _)) = nests
+                .iter()
+                .find(|(_, nest)| nest.owner == entity)
+                .filter(|_| player.score > 0)
+            {
+                options.push(Behaviour::Bank(nest.translation.xy()));
+                utilities.push(personality.caution * player.score as f32 / LARGE_SCORE as f32);
+            }
+
+            if let Some((enemy, _)) = nearest_enemy.filter(|(enemy, _)| {
+                enemy.distance(position) < sight_radius && player.score < LARGE_SCORE
+            }) {
+                options.push(Behaviour::Chase(enemy));
+                utilities.push(personality.aggression * 0.25);
+            }
+
+            bot.behaviour = options
+                .into_iter()
+                .zip(utilities)
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map_or(Behaviour::Idle, |(behaviour, _)| behaviour);
+            dash = matches!(bot.behaviour, Behaviour::Evade(_)) && threat > DASH_THREAT;
+            let reaction_secs = bot.difficulty.reaction_secs;
+            bot.reaction = Timer::from_seconds(reaction_secs, TimerMode::Once);
+        }
+
+        let behaviour = bot.behaviour;
+        let direction = match behaviour {
+            Behaviour::SeekCoin(target) | Behaviour::Chase(target) => {
+                bot.steer_towards(room.nav.as_ref(), position, target)
+            }
+            Behaviour::Bank(nest) if nest.distance(position) < NEST_RADIUS * 0.5 => {
+                bot.path.clear();
+                Vec2::ZERO
+            }
+            Behaviour::Bank(nest) => bot.steer_towards(room.nav.as_ref(), position, nest),
+            Behaviour::Evade(direction) => {
+                bot.path.clear();
+                direction
+            }
+            Behaviour::Idle => {
+                bot.path.clear();
+                Vec2::ZERO
+            }
+        };
+        *input = input_towards(direction);
+        // The dash goes the way the input now points. One that's still cooling down is ignored.
+        if dash {
+            dashes.send(DashRequest { dasher: entity });
+        }
+
+        // Shoot at whoever is closest. Good bots aim where they're going to be.
+        if !bot.fire_cooldown.finished() || !weapon.ready() {
+            continue;
+        }
+        let Some((enemy, enemy_velocity)) =
+            nearest_enemy.filter(|(enemy, _)| enemy.distance(position) < sight_radius)
+        else {
+            continue;
+        };
+        let direction = if bot.difficulty.leads_shots {
+            lead_target(
+                enemy - position,
+                enemy_velocity,
+                room.settings.projectile_speed,
+            )
+        } else {
+            (enemy - position).normalize_or_zero()
+        };
+        let error = (fastrand::f32() * 2.0 - 1.0) * bot.difficulty.aim_error_degrees * PI / 180.0;
+        attacks.send(AttackRequest {
+            shooter: entity,
+            direction: Vec2::from_angle(error).rotate(direction),
+        });
+        bot.fire_cooldown.reset();
+    }
+}
+
+/// How threatened a duck at `position` is (0 to 1), and which way is safest.
+fn threat<'a>(
+    position: Vec2,
+    entity: Entity,
+    is_large: bool,
+    nearest_enemy: Option<Vec2>,
+    projectiles: impl Iterator<Item = (Vec2, &'a Projectile)>,
+) -> (f32, Vec2) {
+    let mut threat = 0.0;
+    let mut away = Vec2::ZERO;
+
+    // Only large ducks have enough coins to be worth running from other ducks.
+    if let Some(enemy) = nearest_enemy.filter(|_| is_large) {
+        let distance = enemy.distance(position);
+        if distance < THREAT_RADIUS {
+            threat = f32::max(threat, 1.0 - distance / THREAT_RADIUS);
+            away += (position - enemy).normalize_or_zero();
+        }
+    }
+
+    for (projectile_position, projectile) in projectiles {
+        if projectile.owner == entity {
+            continue;
+        }
+        let offset = position - projectile_position;
+        let along = offset.dot(projectile.direction);
+        if along <= 0.0 || along > DANGER_DISTANCE {
+            continue;
+        }
+        let across = offset - projectile.direction * along;
+        if across.length() > DANGER_WIDTH {
+            continue;
+        }
+        threat = f32::max(threat, 1.0 - along / DANGER_DISTANCE);
+        // Step out of the projectile's path.
+        away += if across == Vec2::ZERO {
+            projectile.direction.perp()
+        } else {
+            across.normalize()
+        };
+    }
+
+    (threat, away.normalize_or_zero())
+}
+
+/// The direction to shoot in to hit a target moving at a constant velocity.
+/// Falls back to aiming straight at the target if it can't be caught.
+fn lead_target(offset: Vec2, target_velocity: Vec2, projectile_speed: f32) -> Vec2 {
+    // Solve |offset + target_velocity * t| = projectile_speed * t for the earliest t > 0.
+    let a = target_velocity.length_squared() - projectile_speed * projectile_speed;
+    let b = 2.0 * offset.dot(target_velocity);
+    let c = offset.length_squared();
+    let time = if a.abs() < f32::EPSILON {
+        (b.abs() > f32::EPSILON).then(|| -c / b)
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        (discriminant >= 0.0).then(|| {
+            let root = discriminant.sqrt();
+            let (t1, t2) = ((-b - root) / (2.0 * a), (-b + root) / (2.0 * a));
+            if t1 > 0.0 && (t1 < t2 || t2 <= 0.0) {
+                t1
+            } else {
+                t2
+            }
+        })
+    };
+    match time {
+        Some(time) if time > 0.0 => (offset + target_velocity * time).normalize_or_zero(),
+        _ => offset.normalize_or_zero(),
+    }
+}
+
+/// Press the keys that move in roughly this direction.
+fn input_towards(direction: Vec2) -> PlayerInput {
+    PlayerInput {
+        up: direction.y > INPUT_DEADZONE,
+        down: direction.y < -INPUT_DEADZONE,
+        left: direction.x < -INPUT_DEADZONE,
+        right: direction.x > INPUT_DEADZONE,
+    }
+}
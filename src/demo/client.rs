@@ -1,17 +1,19 @@
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::time::UNIX_EPOCH;
 
 use crate::demo::animation::{FacingDirection, PlayerAnimation};
 
 use crate::demo::lib::connection_config;
-use crate::demo::physics::Collider;
-use crate::screens::gameplay::{calculate_score_growth, ScoreText};
+use crate::demo::physics::{Collider, CollisionLayers};
+use crate::screens::gameplay::{Announce, CoinRainWarning, MatchClock, ScoreText};
 use crate::screens::lobby::ToggleReadyEvent;
 use crate::screens::Screen;
 use bevy::{
     diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
     prelude::Vec3,
     prelude::*,
+    window::PrimaryWindow,
 };
 use bevy_mod_reqwest::{BevyReqwest, JsonResponse, ReqwestErrorEvent, ReqwestResponseEvent};
 use renet2_netcode::{
@@ -25,14 +27,21 @@ use bevy_renet2::prelude::{
 };
 use renet2_visualizer::{RenetClientVisualizer, RenetVisualizerStyle};
 
+use super::dash::DashStarted;
+use super::growth::Growth;
 use super::lib::{
-    ClientChannel, NetworkedEntities, Player, PlayerCommand, PlayerInput, ServerChannel,
-    ServerMessages,
+    BotConfig, ClientChannel, CoinKind, MatchResult, MatchSettings, NetworkedEntities, Player,
+    PlayerCommand, PlayerInput, ServerChannel, ServerMessages, DEFAULT_PORT,
 };
-use super::player::PlayerAssets;
+use super::movement::Surface;
+use super::nest::Nest;
+use super::player::{CollectCoin, PlayerAssets};
+use super::practice::PracticeSession;
+use super::projectile::PopOnDespawn;
+use super::transport::{ClientInbox, ClientOutbox};
 
 #[derive(Component)]
-struct ControlledPlayer;
+pub(super) struct ControlledPlayer;
 
 #[derive(Default, Resource)]
 pub struct NetworkMapping(HashMap<Entity, Entity>);
@@ -46,15 +55,79 @@ struct PlayerInfo {
 #[derive(Debug, Default, Resource)]
 pub struct ClientLobby {
     players: HashMap<ClientId, PlayerInfo>,
+    /// Which of the players are bots, and how they play.
+    pub bots: HashMap<ClientId, BotConfig>,
 }
 
 #[derive(Debug, Resource)]
-pub struct CurrentClientId(u64);
+pub struct CurrentClientId(pub u64);
+
+/// The client currently allowed to edit the [`MatchSettings`].
+#[derive(Debug, Default, Resource)]
+pub struct LobbyHost(pub Option<ClientId>);
+
+/// The room this client is in, or why joining one failed.
+#[derive(Debug, Default, Resource)]
+pub struct ClientRoom {
+    pub code: Option<String>,
+    pub error: Option<String>,
+}
+
+/// How everyone did in the last match, best first. Shown in the lobby until the next match
+/// starts.
+#[derive(Debug, Resource)]
+pub struct MatchResults(pub Vec<MatchResult>);
+
+/// The game server to connect to when entering the lobby.
+#[derive(Debug, Resource)]
+pub struct ServerAddress(pub SocketAddr);
+
+impl Default for ServerAddress {
+    fn default() -> Self {
+        Self(SocketAddr::from(([127, 0, 0, 1], DEFAULT_PORT)))
+    }
+}
+
+/// The server the current [`RenetClient`] was set up for.
+#[derive(Debug, Resource)]
+struct ConnectedServer(SocketAddr);
 
 #[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Connected;
 
-pub const PLAYER_BASE_COLLIDER_SIZE: Vec2 = Vec2::new(14., 10.);
+pub const PLAYER_BASE_COLLIDER_RADIUS: f32 = 7.;
+
+/// The round collider at a duck's feet, grown by the coins it carries.
+pub fn player_collider(growth: Growth) -> Collider {
+    Collider::circle(
+        growth.collider_radius,
+        CollisionLayers::PLAYER,
+        CollisionLayers::ALL,
+    )
+}
+
+pub fn projectile_collider() -> Collider {
+    Collider::aabb(
+        Vec2::new(12., 18.),
+        CollisionLayers::PROJECTILE,
+        CollisionLayers::PLAYER | CollisionLayers::STATIC,
+    )
+}
+
+pub fn coin_collider(kind: CoinKind) -> Collider {
+    Collider::circle(11. * kind.size(), CollisionLayers::PICKUP, CollisionLayers::PLAYER)
+}
+
+/// Map objects 0 (dirt), 7 (mud) and 8 (ice) are decoration, 1 is the pond, which only
+/// stops ducks, and the rest are trees and walls, which stop everything.
+pub fn game_object_collider(id: u64, size: Vec2) -> Collider {
+    let mask = match id {
+        0 | 7 | 8 => CollisionLayers::NONE,
+        1 => CollisionLayers::PLAYER,
+        _ => CollisionLayers::PLAYER | CollisionLayers::PROJECTILE,
+    };
+    Collider::aabb(size, CollisionLayers::STATIC, mask)
+}
 
 // #[cfg(feature = "netcode")]
  fn add_netcode_network(app: &mut App) {
@@ -66,7 +139,10 @@ pub const PLAYER_BASE_COLLIDER_SIZE: Vec2 = Vec2::new(14., 10.);
 
     app.add_plugins(NetcodeClientPlugin);
 
-    app.configure_sets(Update, Connected.run_if(client_connected));
+    app.configure_sets(
+        Update,
+        Connected.run_if(client_connected.or(resource_exists::<PracticeSession>)),
+    );
 
     // If any error is found we just panic
     #[allow(clippy::never_loop)]
@@ -155,10 +231,25 @@ pub const PLAYER_BASE_COLLIDER_SIZE: Vec2 = Vec2::new(14., 10.);
             });
     }
     #[cfg(not(target_family = "wasm"))]
-    fn connect_udp(mut commands: Commands) {
+    fn connect_udp(
+        mut commands: Commands,
+        server_address: Res<ServerAddress>,
+        connected: Option<Res<ConnectedServer>>,
+        client: Option<Res<RenetClient>>,
+        transport: Option<ResMut<NetcodeClientTransport>>,
+    ) {
+        let server_addr = server_address.0;
+        // Back from a match, we're still connected to the right server.
+        if connected.is_some_and(|connected| connected.0 == server_addr)
+            && client.is_some_and(|client| !client.is_disconnected())
+        {
+            return;
+        }
+        if let Some(mut transport) = transport {
+            transport.disconnect();
+        }
         println!("[CLIENT] Connecting to server...");
-        let server_addr = "127.0.0.1:5000".parse().unwrap();
-        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
 
         let client = RenetClient::new(connection_config(), false);
 
@@ -178,6 +269,7 @@ pub const PLAYER_BASE_COLLIDER_SIZE: Vec2 = Vec2::new(14., 10.);
         let transport = NetcodeClientTransport::new(current_time, authentication, NativeSocket::new(socket).unwrap()).unwrap();
         commands.insert_resource(transport);
         commands.insert_resource(client);
+        commands.insert_resource(ConnectedServer(server_addr));
 
         commands.insert_resource(CurrentClientId(client_id));
         println!("[CLIENT] Connected!");
@@ -187,15 +279,17 @@ pub const PLAYER_BASE_COLLIDER_SIZE: Vec2 = Vec2::new(14., 10.);
 
     #[cfg(target_family = "wasm")]
     app.add_systems(
-        Update,
-        connect_wasm.run_if(in_state(Screen::Lobby).and(run_once)),
+        OnEnter(Screen::Lobby),
+        connect_wasm.run_if(not(resource_exists::<RenetClient>)),
     );
 
     #[cfg(not(target_family = "wasm"))]
     app.add_systems(
-        Update,
-        connect_udp.run_if(in_state(Screen::Lobby).and(run_once)),
+        OnEnter(Screen::Lobby),
+        connect_udp.run_if(not(resource_exists::<PracticeSession>)),
     );
+
+    app.add_systems(OnEnter(Screen::Title), disconnect);
 }
 
 pub(super) fn plugins(app: &mut App) {
@@ -210,19 +304,31 @@ pub(super) fn plugins(app: &mut App) {
     app.add_event::<PlayerCommand>();
 
     app.insert_resource(ClientLobby::default());
+    app.insert_resource(LobbyHost::default());
+    app.insert_resource(ClientRoom::default());
+    app.init_resource::<ServerAddress>();
+    app.init_resource::<ClientInbox>();
+    app.init_resource::<ClientOutbox>();
+    app.insert_resource(MatchSettings::default());
     app.insert_resource(PlayerInput::default());
     app.insert_resource(NetworkMapping::default());
 
+    app.add_systems(OnEnter(Screen::Title), forget_room);
     app.add_systems(Update, (player_input).run_if(in_state(Screen::Gameplay)));
     app.add_systems(Update, (player_read_input).run_if(in_state(Screen::Lobby)));
     app.add_systems(
         Update,
         (
-            client_send_input,
-            update_score_text,
-            client_send_player_commands,
-            client_sync_players,
+            receive_from_server.run_if(client_connected),
+            (
+                client_send_input,
+                update_score_text,
+                client_send_player_commands,
+                client_sync_players,
+            ),
+            send_to_server.run_if(client_connected),
         )
+            .chain()
             .in_set(Connected),
     );
 
@@ -233,10 +339,42 @@ pub(super) fn plugins(app: &mut App) {
     // app.add_systems(Startup, (setup_target));
     app.add_systems(
         Update,
-        update_visulizer_system.run_if(in_state(Screen::Gameplay)),
+        update_visulizer_system.run_if(in_state(Screen::Gameplay).and(client_connected)),
     );
 }
 
+/// Say goodbye to the server, so it frees our duck straight away instead of timing us out.
+pub fn disconnect(mut commands: Commands, transport: Option<ResMut<NetcodeClientTransport>>) {
+    if let Some(mut transport) = transport {
+        println!("[CLIENT] Disconnecting from server.");
+        transport.disconnect();
+    }
+    commands.remove_resource::<NetcodeClientTransport>();
+    commands.remove_resource::<RenetClient>();
+    commands.remove_resource::<ConnectedServer>();
+}
+
+/// Back on the title screen we're in no room, and whatever the server told us about it is stale.
+fn forget_room(
+    mut commands: Commands,
+    mut room: ResMut<ClientRoom>,
+    mut lobby: ResMut<ClientLobby>,
+    mut network_mapping: ResMut<NetworkMapping>,
+    mut lobby_host: ResMut<LobbyHost>,
+    mut inbox: ResMut<ClientInbox>,
+    mut outbox: ResMut<ClientOutbox>,
+) {
+    *room = ClientRoom::default();
+    *lobby = ClientLobby::default();
+    network_mapping.0.clear();
+    lobby_host.0 = None;
+    // Nothing still in flight belongs to the next session.
+    *inbox = ClientInbox::default();
+    *outbox = ClientOutbox::default();
+    commands.remove_resource::<CurrentClientId>();
+    commands.remove_resource::<MatchResults>();
+}
+
 fn update_visulizer_system(
     mut egui_contexts: EguiContexts,
     mut visualizer: ResMut<RenetClientVisualizer<200>>,
@@ -253,10 +391,19 @@ fn update_visulizer_system(
     }
 }
 
+/// Right stick deflection below this doesn't count as aiming.
+const STICK_AIM_DEADZONE: f32 = 0.3;
+
 fn player_input(
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    controlled_player: Query<(&Transform, &FacingDirection), With<ControlledPlayer>>,
     mut player_input: ResMut<PlayerInput>,
     mut player_commands: EventWriter<PlayerCommand>,
+    mut last_aim: Local<Option<Vec2>>,
 ) {
     player_input.left =
         keyboard_input.pressed(KeyCode::KeyA) || keyboard_input.pressed(KeyCode::ArrowLeft);
@@ -267,9 +414,54 @@ fn player_input(
     player_input.down =
         keyboard_input.pressed(KeyCode::KeyS) || keyboard_input.pressed(KeyCode::ArrowDown);
 
-    if keyboard_input.just_pressed(KeyCode::Space) {
-        player_commands.send(PlayerCommand::BasicAttack);
+    let gamepad_dashed = gamepads
+        .iter()
+        .any(|gamepad| gamepad.just_pressed(GamepadButton::LeftTrigger2));
+    if keyboard_input.just_pressed(KeyCode::ShiftLeft)
+        || mouse_input.just_pressed(MouseButton::Right)
+        || gamepad_dashed
+    {
+        player_commands.send(PlayerCommand::Dash);
+    }
+
+    let gamepad_fired = gamepads
+        .iter()
+        .any(|gamepad| gamepad.just_pressed(GamepadButton::RightTrigger2));
+    if !(keyboard_input.just_pressed(KeyCode::Space)
+        || mouse_input.just_pressed(MouseButton::Left)
+        || gamepad_fired)
+    {
+        return;
+    }
+    let Ok((transform, facing)) = controlled_player.get_single() else {
+        return;
+    };
+
+    // Prefer the right stick, then the mouse cursor, then wherever the duck is facing.
+    let stick_aim = gamepads
+        .iter()
+        .map(|gamepad| gamepad.right_stick())
+        .find(|stick| stick.length() > STICK_AIM_DEADZONE);
+    let cursor_aim = || {
+        let cursor = windows.get_single().ok()?.cursor_position()?;
+        let (camera, camera_transform) = cameras.get_single().ok()?;
+        let target = camera.viewport_to_world_2d(camera_transform, cursor).ok()?;
+        Some(target - transform.translation.xy())
+    };
+    let facing_aim = (facing.0 != Vec2::ZERO).then_some(facing.0);
+    // Ducks face nowhere while standing still, so remember the last way we aimed.
+    let aim = stick_aim
+        .or_else(cursor_aim)
+        .or(facing_aim)
+        .or(*last_aim)
+        .unwrap_or(Vec2::Y);
+    if aim == Vec2::ZERO {
+        return;
     }
+    *last_aim = Some(aim);
+    player_commands.send(PlayerCommand::BasicAttack {
+        aim: aim.to_array(),
+    });
 }
 fn player_read_input(
     keyboard_input: Res<ButtonInput<KeyCode>>,
@@ -280,19 +472,33 @@ fn player_read_input(
     }
 }
 
-fn client_send_input(player_input: Res<PlayerInput>, mut client: ResMut<RenetClient>) {
-    let input_message = bincode::serialize(&*player_input).unwrap();
-
-    client.send_message(ClientChannel::Input, input_message);
+fn client_send_input(player_input: Res<PlayerInput>, mut outbox: ResMut<ClientOutbox>) {
+    outbox.input = Some(*player_input);
 }
 
 fn client_send_player_commands(
     mut player_commands: EventReader<PlayerCommand>,
-    mut client: ResMut<RenetClient>,
+    mut outbox: ResMut<ClientOutbox>,
 ) {
-    for command in player_commands.read() {
-        let command_message = bincode::serialize(command).unwrap();
-        client.send_message(ClientChannel::Command, command_message);
+    outbox.commands.extend(player_commands.read().cloned());
+}
+
+/// Hand everything renet received from the server to the [`ClientInbox`].
+fn receive_from_server(mut client: ResMut<RenetClient>, mut inbox: ResMut<ClientInbox>) {
+    for channel in [ServerChannel::ServerMessages, ServerChannel::NetworkedEntities] {
+        while let Some(message) = client.receive_message(channel) {
+            inbox.push(channel, message.to_vec());
+        }
+    }
+}
+
+/// Send what's in the [`ClientOutbox`] to the server through renet.
+fn send_to_server(mut client: ResMut<RenetClient>, mut outbox: ResMut<ClientOutbox>) {
+    if let Some(input) = outbox.input.take() {
+        client.send_message(ClientChannel::Input, bincode::serialize(&input).unwrap());
+    }
+    for command in outbox.commands.drain(..) {
+        client.send_message(ClientChannel::Command, bincode::serialize(&command).unwrap());
     }
 }
 
@@ -300,7 +506,7 @@ pub fn client_sync_players(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    mut client: ResMut<RenetClient>,
+    mut inbox: ResMut<ClientInbox>,
     client_id: Res<CurrentClientId>,
     mut lobby: ResMut<ClientLobby>,
     mut network_mapping: ResMut<NetworkMapping>,
@@ -309,11 +515,24 @@ pub fn client_sync_players(
     mut player_data: Query<&mut Player>,
     mut toggles: EventWriter<ToggleReadyEvent>,
     mut next_screen: ResMut<NextState<Screen>>,
+    mut match_settings: ResMut<MatchSettings>,
+    mut lobby_host: ResMut<LobbyHost>,
+    mut room: ResMut<ClientRoom>,
 ) {
     let client_id = client_id.0;
-    while let Some(message) = client.receive_message(ServerChannel::ServerMessages) {
+    while let Some(message) = inbox.receive(ServerChannel::ServerMessages) {
         let server_message = bincode::deserialize(&message).unwrap();
         match server_message {
+            ServerMessages::RoomJoined { code } => {
+                println!("Joined room {}.", code);
+                lobby.bots.clear();
+                room.code = Some(code);
+                room.error = None;
+            }
+            ServerMessages::RoomJoinFailed { reason } => {
+                println!("Could not join room: {}", reason);
+                room.error = Some(reason);
+            }
             ServerMessages::PlayerCreate {
                 id,
                 translation,
@@ -336,6 +555,7 @@ pub fn client_sync_players(
                     Player {
                         id,
                         score: 0,
+                        banked: 0,
                         is_ready,
                     },
                     Sprite {
@@ -346,11 +566,7 @@ pub fn client_sync_players(
                         }),
                         ..default()
                     },
-                    Collider {
-                        size: Vec2::new(14., 24.),
-                        collides_with_player: true,
-                        collides_with_projectile: true,
-                    },
+                    player_collider(Growth::from_score(0, &match_settings)),
                     FacingDirection(Vec2::new(0.0, 1.0)),
                     Transform::from_translation(Vec3::from_array(translation)),
                     player_animation,
@@ -370,6 +586,7 @@ pub fn client_sync_players(
             }
             ServerMessages::PlayerRemove { id } => {
                 println!("Player {} disconnected.", id);
+                lobby.bots.remove(&id);
                 if let Some(PlayerInfo {
                     server_entity,
                     client_entity,
@@ -379,7 +596,11 @@ pub fn client_sync_players(
                     network_mapping.0.remove(&server_entity);
                 }
             }
-            ServerMessages::SpawnGameObject { id, translation } => {
+            ServerMessages::SpawnGameObject {
+                entity,
+                id,
+                translation,
+            } => {
                 println!("Object {} spawned at {:?}.", id, translation);
                 let obj_collider_sizes = [
                     Vec2::new(0., 0.),
@@ -389,8 +610,11 @@ pub fn client_sync_players(
                     Vec2::new(94., 48.),
                     Vec2::new(32., 80.),
                     Vec2::new(32., 114.),
+                    Vec2::new(0., 0.),
+                    Vec2::new(0., 0.),
                 ];
-                commands.spawn((
+                let surface = Surface::from_game_object_id(id);
+                let mut object_entity = commands.spawn((
                     Name::new("Dirt"),
                     Sprite {
                         image: match id {
@@ -401,19 +625,22 @@ pub fn client_sync_players(
                             4 => player_assets.wall_h_large.clone(),
                             5 => player_assets.wall_v_small.clone(),
                             6 => player_assets.wall_v_large.clone(),
+                            7 | 8 => player_assets.dirt_patch.clone(),
                             _ => unreachable!(),
                         },
+                        color: surface.map_or(Color::WHITE, Surface::tint),
                         ..default()
                     },
-                    Collider {
-                        size: obj_collider_sizes[id as usize] * 1.5,
-                        collides_with_player: id != 0,
-                        collides_with_projectile: id >= 2,
-                    },
+                    game_object_collider(id, obj_collider_sizes[id as usize] * 1.5),
                     Transform::from_translation(Vec3::from_array(translation))
                         .with_scale(Vec3::new(1.5, 1.5, 1.)),
                     StateScoped(Screen::Gameplay),
                 ));
+                if let Some(surface) = surface {
+                    object_entity.insert(surface);
+                }
+
+                network_mapping.0.insert(entity, object_entity.id());
             }
             ServerMessages::SpawnProjectile {
                 entity,
@@ -426,13 +653,10 @@ pub fn client_sync_players(
                         custom_size: Some(Vec2::new(12., 18.)),
                         ..default()
                     },
-                    Collider {
-                        size: Vec2::new(12., 18.),
-                        collides_with_player: true,
-                        collides_with_projectile: true,
-                    },
+                    projectile_collider(),
                     Transform::from_translation(translation.into())
                         .with_rotation(Quat::from_rotation_z(angle)),
+                    PopOnDespawn,
                 ));
 
                 network_mapping.0.insert(entity, projectile_entity.id());
@@ -440,23 +664,60 @@ pub fn client_sync_players(
             ServerMessages::SpawnCoin {
                 entity,
                 translation,
+                kind,
             } => {
                 let coin_entity = commands.spawn((
                     Sprite {
-                        image: player_assets.coin.clone(),
+                        image: player_assets.coin_image(kind),
                         ..default()
                     },
-                    Collider {
-                        size: Vec2::new(20., 24.),
-                        collides_with_player: true,
-                        collides_with_projectile: false,
-                    },
+                    coin_collider(kind),
                     Transform::from_translation(translation.into())
-                        .with_scale(Vec3::new(1.5, 1.5, 1.)),
+                        .with_scale(Vec2::splat(1.5 * kind.size()).extend(1.)),
                 ));
+                if kind.announced() {
+                    commands.trigger(Announce {
+                        message: "A golden lily pad appeared!".to_string(),
+                    });
+                }
 
                 network_mapping.0.insert(entity, coin_entity.id());
             }
+            ServerMessages::SpawnNest {
+                entity,
+                owner,
+                translation,
+            } => {
+                let Some(owner) = network_mapping.0.get(&owner) else {
+                    continue;
+                };
+                let nest_entity = commands.spawn((
+                    Name::new("Nest"),
+                    Nest { owner: *owner },
+                    Transform::from_translation(translation.into()),
+                    StateScoped(Screen::Gameplay),
+                ));
+
+                network_mapping.0.insert(entity, nest_entity.id());
+            }
+            ServerMessages::CoinRainIncoming { secs } => {
+                commands.trigger(CoinRainWarning { secs });
+            }
+            ServerMessages::CoinCollected { coin, by } => {
+                if let (Some(coin), Some(by)) =
+                    (network_mapping.0.get(&coin), network_mapping.0.get(&by))
+                {
+                    commands.trigger(CollectCoin {
+                        coin: *coin,
+                        by: *by,
+                    });
+                }
+            }
+            ServerMessages::Dashed { entity } => {
+                if let Some(duck) = network_mapping.0.get(&entity) {
+                    commands.trigger(DashStarted { duck: *duck });
+                }
+            }
             ServerMessages::DespawnEntity { entity } => {
                 if let Some(entity) = network_mapping.0.remove(&entity) {
                     commands.entity(entity).despawn();
@@ -474,15 +735,47 @@ pub fn client_sync_players(
                     }
                 }
             }
+            ServerMessages::SetMatchSettings { host, settings } => {
+                *match_settings = settings;
+                lobby_host.0 = host;
+            }
+            ServerMessages::SetBotConfig { id, config } => {
+                lobby.bots.insert(id, config);
+            }
             ServerMessages::StartGame => {
                 println!("Starting game!");
+                commands.remove_resource::<MatchResults>();
                 next_screen.set(Screen::Gameplay);
             }
+            ServerMessages::EndGame { results } => {
+                println!("Game over! Results: {:?}", results);
+                // The server has already set up the next match, so nothing from this one
+                // is coming back.
+                for (_, entity) in network_mapping.0.drain() {
+                    if let Some(entity) = commands.get_entity(entity) {
+                        entity.despawn_recursive();
+                    }
+                }
+                lobby.players.clear();
+                commands.insert_resource(MatchResults(results));
+                next_screen.set(Screen::Lobby);
+                // The rest is for the lobby. Ducks spawned now would leave with the
+                // Gameplay screen.
+                break;
+            }
         }
     }
 
-    while let Some(message) = client.receive_message(ServerChannel::NetworkedEntities) {
+    while let Some(message) = inbox.receive(ServerChannel::NetworkedEntities) {
         let networked_entities: NetworkedEntities = bincode::deserialize(&message).unwrap();
+        // The server's clock is the one that ends the match; ours just counts down between
+        // updates.
+        match networked_entities.match_secs_left {
+            Some(secs) => {
+                commands.insert_resource(MatchClock(Timer::from_seconds(secs, TimerMode::Once)))
+            }
+            None => commands.remove_resource::<MatchClock>(),
+        }
         for i in 0..networked_entities.entities.len() {
             if let Some(entity) = network_mapping.0.get(&networked_entities.entities[i]) {
                 let translation = networked_entities.translations[i].into();
@@ -494,14 +787,25 @@ pub fn client_sync_players(
                 if let Some(direction) = maybe_direction {
                     commands.entity(*entity).insert(FacingDirection(direction));
                 }
+                if let Some(weapon) = networked_entities.weapons[i] {
+                    commands.entity(*entity).insert(weapon);
+                }
+                if let Some(hit_state) = networked_entities.hit_states[i] {
+                    commands.entity(*entity).insert(hit_state);
+                }
                 if let Some(score) = networked_entities.score[i] {
                     if let Ok(mut player) = player_data.get_mut(*entity) {
-                        player.score = score;
-                        transform.scale = Vec3::new(
-                            1.0 + calculate_score_growth(score),
-                            1.0 + calculate_score_growth(score),
-                            1.0,
-                        );
+                        let growth = Growth::from_score(score, &match_settings);
+                        transform.scale = Vec2::splat(growth.scale).extend(1.0);
+                        if player.score != score {
+                            player.score = score;
+                            commands.entity(*entity).insert(player_collider(growth));
+                        }
+                    }
+                }
+                if let Some(banked) = networked_entities.banked[i] {
+                    if let Ok(mut player) = player_data.get_mut(*entity) {
+                        player.banked = banked;
                     }
                 }
                 commands.entity(*entity).insert(transform);
@@ -519,6 +823,6 @@ fn update_score_text(
             return;
         };
 
-        text.0 = format!("Coins: {}", player.score);
+        text.0 = format!("Carried: {}  Banked: {}", player.score, player.banked);
     }
 }
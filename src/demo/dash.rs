@@ -0,0 +1,199 @@
+//! Dashing: a quick burst the way a duck is going, that projectiles can't hit. It has a
+//! cooldown, and bigger ducks dash shorter. The server moves the duck with a [`Dash`]; clients
+//! hear about it through [`ServerMessages::Dashed`] and draw a trail of afterimages.
+
+use bevy::prelude::*;
+
+use crate::{audio::SoundEffect, screens::Screen, AppSet};
+
+use super::{
+    growth::Growth,
+    hit::{Invulnerable, Stunned},
+    lib::{Player, PlayerInput, RoomId, ServerChannel, ServerMessages, Velocity},
+    movement::Dash,
+    player::PlayerAssets,
+    server::ServerLobby,
+    transport::ServerOutbox,
+};
+
+/// How long a dash lasts. Its distance depends on the duck.
+const DASH_SECS: f32 = 0.15;
+const DASH_COOLDOWN_SECS: f32 = 1.5;
+/// How long projectiles fly through a dashing duck.
+const DASH_INVULNERABLE_SECS: f32 = 0.25;
+/// A dash leaves an afterimage this often.
+const AFTERIMAGE_INTERVAL_SECS: f32 = 0.03;
+const AFTERIMAGE_SECS: f32 = 0.2;
+
+/// Client side: draw and play dashes.
+pub(super) fn plugin(app: &mut App) {
+    app.add_observer(start_dash_effect);
+    app.add_systems(
+        Update,
+        (leave_afterimages, fade_afterimages)
+            .in_set(AppSet::Update)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Server side: dash for whoever asked to, and count down their cooldowns.
+pub(super) fn server_plugin(app: &mut App) {
+    app.add_event::<DashRequest>();
+    app.add_systems(Update, (handle_dash_requests, tick_dash_cooldowns));
+}
+
+/// A duck wants to dash. Humans and bots both go through this.
+#[derive(Debug, Event)]
+pub struct DashRequest {
+    pub dasher: Entity,
+}
+
+/// A duck that dashed recently and can't again until the timer runs out.
+#[derive(Component, Debug)]
+pub struct DashCooldown(Timer);
+
+/// Sent on the client when a duck starts dashing.
+#[derive(Debug, Event)]
+pub struct DashStarted {
+    pub duck: Entity,
+}
+
+/// A duck that's dashing, leaving afterimages behind.
+#[derive(Component, Debug)]
+struct DashEffect {
+    duration: Timer,
+    afterimage: Timer,
+}
+
+/// A fading copy of a dashing duck.
+#[derive(Component, Debug)]
+struct Afterimage(Timer);
+
+/// Dash the way each duck is going, unless it's stunned, standing still or still cooling
+/// down.
+#[allow(clippy::type_complexity)]
+fn handle_dash_requests(
+    mut events: EventReader<DashRequest>,
+    mut commands: Commands,
+    mut outbox: ResMut<ServerOutbox>,
+    lobby: Res<ServerLobby>,
+    ducks: Query<
+        (
+            &Player,
+            &PlayerInput,
+            &Velocity,
+            &RoomId,
+            Option<&Invulnerable>,
+        ),
+        (Without<Stunned>, Without<DashCooldown>, Without<Dash>),
+    >,
+) {
+    for event in events.read() {
+        let Ok((player, input, velocity, room_id, invulnerable)) = ducks.get(event.dasher) else {
+            continue;
+        };
+        let Some(room) = lobby.rooms.get(room_id).filter(|room| room.in_game()) else {
+            continue;
+        };
+        let Some(direction) = input
+            .direction()
+            .try_normalize()
+            .or_else(|| velocity.0.try_normalize())
+        else {
+            continue;
+        };
+        let growth = Growth::from_score(player.score, &room.settings);
+        let mut dasher = commands.entity(event.dasher);
+        dasher.insert((
+            Dash::new(direction, growth.dash_distance, DASH_SECS),
+            DashCooldown(Timer::from_seconds(DASH_COOLDOWN_SECS, TimerMode::Once)),
+        ));
+        // Don't cut short the protection from a hit.
+        if invulnerable
+            .is_none_or(|invulnerable| invulnerable.remaining_secs() < DASH_INVULNERABLE_SECS)
+        {
+            dasher.insert(Invulnerable::for_secs(DASH_INVULNERABLE_SECS));
+        }
+        room.send(
+            &mut outbox,
+            ServerChannel::ServerMessages,
+            &ServerMessages::Dashed {
+                entity: event.dasher,
+            },
+        );
+    }
+}
+
+fn tick_dash_cooldowns(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut cooldowns: Query<(Entity, &mut DashCooldown)>,
+) {
+    for (entity, mut cooldown) in &mut cooldowns {
+        if cooldown.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<DashCooldown>();
+        }
+    }
+}
+
+fn start_dash_effect(
+    trigger: Trigger<DashStarted>,
+    mut commands: Commands,
+    player_assets: Option<Res<PlayerAssets>>,
+) {
+    let Some(mut duck) = commands.get_entity(trigger.event().duck) else {
+        return;
+    };
+    duck.insert(DashEffect {
+        duration: Timer::from_seconds(DASH_SECS, TimerMode::Once),
+        afterimage: Timer::from_seconds(AFTERIMAGE_INTERVAL_SECS, TimerMode::Repeating),
+    });
+    let Some(player_assets) = player_assets else {
+        return;
+    };
+    commands.spawn((
+        AudioPlayer(player_assets.dash.clone()),
+        PlaybackSettings::DESPAWN,
+        SoundEffect,
+    ));
+}
+
+fn leave_afterimages(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut ducks: Query<(Entity, &mut DashEffect, &Transform, &Sprite)>,
+) {
+    for (entity, mut effect, transform, sprite) in &mut ducks {
+        if effect.afterimage.tick(time.delta()).just_finished() {
+            commands.spawn((
+                Name::new("Afterimage"),
+                Sprite {
+                    color: sprite.color.with_alpha(0.5),
+                    ..sprite.clone()
+                },
+                // Just behind the duck.
+                transform.with_translation(transform.translation - Vec3::Z * 0.1),
+                Afterimage(Timer::from_seconds(AFTERIMAGE_SECS, TimerMode::Once)),
+                StateScoped(Screen::Gameplay),
+            ));
+        }
+        if effect.duration.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<DashEffect>();
+        }
+    }
+}
+
+fn fade_afterimages(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut afterimages: Query<(Entity, &mut Afterimage, &mut Sprite)>,
+) {
+    for (entity, mut afterimage, mut sprite) in &mut afterimages {
+        if afterimage.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let alpha = 0.5 * (1.0 - afterimage.0.fraction());
+        sprite.color.set_alpha(alpha);
+    }
+}
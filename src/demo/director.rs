@@ -0,0 +1,158 @@
+//! Where and when coins appear. Each room's [`CoinDirector`] drops coins on a steady beat,
+//! favours the map's hotspots, keeps them out of walls, the pond and ducks, and every so
+//! often announces a coin rain.
+
+use std::{collections::HashMap, f32::consts::TAU, time::Duration};
+
+use bevy::prelude::*;
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+
+use super::{
+    client::coin_collider,
+    lib::{CoinKind, MatchSettings, RoomId, ServerChannel, ServerMessages},
+    physics::{check_collision, Collider, SpatialHash},
+    player::Coin,
+    server::{spawn_coin, ServerLobby},
+    transport::ServerOutbox,
+};
+
+/// Coins that don't go to a hotspot spawn anywhere in here.
+const SPAWN_HALF_EXTENTS: Vec2 = Vec2::new(750., 400.);
+/// Give up on a coin when this many spots in a row turn out to be taken.
+const SPAWN_ATTEMPTS: usize = 16;
+/// How long before a coin rain the players hear about it.
+const RAIN_WARNING_SECS: f32 = 3.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(FixedUpdate, direct_coins);
+}
+
+/// An area of the map where coins are more likely to show up.
+#[derive(Debug, Clone, Copy)]
+pub struct Hotspot {
+    pub center: Vec2,
+    pub radius: f32,
+    /// How likely this hotspot is to get a coin, compared to the others.
+    pub weight: f32,
+}
+
+/// Decides when and where a room's coins spawn, following its [`MatchSettings`].
+#[derive(Debug, Default)]
+pub struct CoinDirector {
+    /// Set whenever the map is generated.
+    pub hotspots: Vec<Hotspot>,
+    spawner: Timer,
+    rain: Timer,
+    rain_announced: bool,
+}
+
+impl CoinDirector {
+    /// Start the clocks for a new match.
+    pub fn start(&mut self, settings: &MatchSettings) {
+        self.spawner = Timer::from_seconds(settings.coin_spawn_interval_secs, TimerMode::Repeating);
+        self.rain = Timer::from_seconds(settings.coin_rain_interval_secs, TimerMode::Repeating);
+        self.rain_announced = false;
+    }
+
+    /// How many coins are due this tick, and in how many seconds the next coin rain starts
+    /// if it's time to announce it.
+    fn tick(&mut self, delta: Duration, settings: &MatchSettings) -> (u32, Option<f32>) {
+        let mut due = self.spawner.tick(delta).times_finished_this_tick();
+        if settings.coin_rain_coins == 0 {
+            return (due, None);
+        }
+        let mut announce = None;
+        if self.rain.tick(delta).just_finished() {
+            due += settings.coin_rain_coins;
+            self.rain_announced = false;
+        } else if !self.rain_announced && self.rain.remaining_secs() <= RAIN_WARNING_SECS {
+            self.rain_announced = true;
+            announce = Some(self.rain.remaining_secs());
+        }
+        (due, announce)
+    }
+
+    /// A random spot for a coin, which may well be taken.
+    fn pick_spot(&self, rng: &mut impl Rng, settings: &MatchSettings) -> Vec2 {
+        if !self.hotspots.is_empty() && rng.gen_bool(settings.coin_hotspot_share as f64) {
+            if let Ok(weights) = WeightedIndex::new(self.hotspots.iter().map(|h| h.weight)) {
+                let hotspot = self.hotspots[weights.sample(rng)];
+                // Spread evenly over the disc, not bunched up in the middle.
+                let offset = Vec2::from_angle(rng.gen_range(0.0..TAU))
+                    * hotspot.radius
+                    * rng.gen::<f32>().sqrt();
+                return (hotspot.center + offset).clamp(-SPAWN_HALF_EXTENTS, SPAWN_HALF_EXTENTS);
+            }
+        }
+        Vec2::new(
+            rng.gen_range(-SPAWN_HALF_EXTENTS.x..SPAWN_HALF_EXTENTS.x),
+            rng.gen_range(-SPAWN_HALF_EXTENTS.y..SPAWN_HALF_EXTENTS.y),
+        )
+    }
+}
+
+/// Spawn every room's due coins, up to its cap, on spots nothing else is in.
+fn direct_coins(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut lobby: ResMut<ServerLobby>,
+    mut outbox: ResMut<ServerOutbox>,
+    spatial_hash: Res<SpatialHash>,
+    colliders: Query<(&Transform, &Collider)>,
+    coins: Query<&RoomId, With<Coin>>,
+) {
+    let mut coin_counts: HashMap<RoomId, usize> = HashMap::new();
+    for room_id in coins.iter() {
+        *coin_counts.entry(*room_id).or_default() += 1;
+    }
+
+    let mut rng = rand::thread_rng();
+    let kinds = WeightedIndex::new(CoinKind::ALL.map(CoinKind::spawn_weight)).unwrap();
+    for (room_id, room) in lobby.rooms.iter_mut() {
+        if !room.in_game() {
+            continue;
+        }
+        let (due, rain_in) = room.coin_director.tick(time.delta(), &room.settings);
+        if let Some(secs) = rain_in {
+            room.send(
+                &mut outbox,
+                ServerChannel::ServerMessages,
+                &ServerMessages::CoinRainIncoming { secs },
+            );
+        }
+
+        let live = coin_counts.get(room_id).copied().unwrap_or_default();
+        let room_left = (room.settings.max_coins as usize).saturating_sub(live);
+        // Coins spawned this tick aren't in the spatial hash yet.
+        let mut spawned: Vec<Vec2> = vec![];
+        for _ in 0..(due as usize).min(room_left) {
+            let kind = CoinKind::ALL[kinds.sample(&mut rng)];
+            let coin = coin_collider(kind);
+            let is_free = |spot: Vec2| {
+                spawned
+                    .iter()
+                    .all(|other| other.distance(spot) >= coin.size().x)
+                    && spatial_hash
+                        .query(Some(*room_id), spot, coin.size())
+                        .into_iter()
+                        .filter_map(|entity| colliders.get(entity).ok())
+                        .all(|(transform, collider)| {
+                            !check_collision(
+                                &spot.extend(0.),
+                                &coin,
+                                &transform.translation,
+                                collider,
+                            )
+                        })
+            };
+            let Some(spot) = (0..SPAWN_ATTEMPTS)
+                .map(|_| room.coin_director.pick_spot(&mut rng, &room.settings))
+                .find(|spot| is_free(*spot))
+            else {
+                continue;
+            };
+            spawned.push(spot);
+            spawn_coin(&mut commands, &mut outbox, room, spot.extend(3.), kind);
+        }
+    }
+}
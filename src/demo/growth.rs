@@ -0,0 +1,47 @@
+//! How the coins a duck carries change it. Bigger ducks are easier to hit and slower, so
+//! carrying a lot is a risk as well as a lead. The server and the clients both work it out
+//! from the same [`MatchSettings`], so they always agree on how big a duck is.
+
+use super::{client::PLAYER_BASE_COLLIDER_RADIUS, lib::MatchSettings};
+
+/// How fast a duck with no coins walks.
+pub const PLAYER_MOVE_SPEED: f32 = 300.0;
+/// How quickly a duck with no coins gets up to speed, and comes to a stop.
+pub const PLAYER_ACCELERATION: f32 = 2400.0;
+pub const PLAYER_DECELERATION: f32 = 3000.0;
+/// How far a duck with no coins dashes.
+pub const PLAYER_DASH_DISTANCE: f32 = 150.0;
+
+/// Everything about a duck that depends on how many coins it carries.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Growth {
+    /// Multiplies the duck's sprite and collider. 1 for a duck with no coins.
+    pub scale: f32,
+    pub collider_radius: f32,
+    /// See [`MovementController::max_speed`](super::movement::MovementController::max_speed).
+    pub max_speed: f32,
+    /// Bigger ducks are heavier, so they take longer to get going and to stop.
+    pub acceleration: f32,
+    pub deceleration: f32,
+    /// How hard the duck is to push around, which goes with its area.
+    pub mass: f32,
+    /// Big ducks dash shorter, so growing stays a trade-off.
+    pub dash_distance: f32,
+}
+
+impl Growth {
+    /// How a duck carrying `score` coins looks and moves.
+    pub fn from_score(score: i64, settings: &MatchSettings) -> Self {
+        let growth = settings.growth_curve.apply(score as f32) * settings.growth_factor;
+        let scale = (1.0 + growth).clamp(1.0, settings.max_growth_scale.max(1.0));
+        Self {
+            scale,
+            collider_radius: PLAYER_BASE_COLLIDER_RADIUS * scale,
+            max_speed: PLAYER_MOVE_SPEED * scale.powf(-settings.size_slowdown),
+            acceleration: PLAYER_ACCELERATION / scale,
+            deceleration: PLAYER_DECELERATION / scale,
+            mass: scale * scale,
+            dash_distance: PLAYER_DASH_DISTANCE / scale,
+        }
+    }
+}
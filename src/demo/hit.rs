@@ -0,0 +1,119 @@
+//! What getting shot does to a duck. It's knocked back along the shot, stunned for a moment,
+//! and then can't be hit again for a little while, so focus fire can't take all its coins
+//! at once. The server replicates a [`HitState`] so clients can show it.
+
+use bevy::prelude::*;
+
+use crate::{screens::Screen, AppSet};
+
+use super::lib::{HitState, Velocity};
+
+/// How fast a hit sends a duck flying. It slows down like any duck that stops walking.
+const KNOCKBACK_SPEED: f32 = 600.0;
+/// How long a hit duck can't walk or shoot.
+const STUN_SECS: f32 = 0.3;
+/// How long a hit duck can't be hit again, counting the stun.
+const INVULNERABLE_SECS: f32 = 1.5;
+/// How many times a second invulnerable ducks blink.
+const BLINK_HZ: f32 = 8.0;
+const STUNNED_TINT: Color = Color::srgb(1.0, 0.6, 0.6);
+
+/// Client side: show which ducks were just hit.
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        show_hit_ducks
+            .in_set(AppSet::Update)
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Server side: let hit ducks recover.
+pub(super) fn server_plugin(app: &mut App) {
+    app.add_systems(Update, recover);
+}
+
+/// A duck that can't walk or shoot until the timer runs out.
+#[derive(Component, Debug)]
+pub struct Stunned(Timer);
+
+/// A duck projectiles fly straight through until the timer runs out.
+#[derive(Component, Debug)]
+pub struct Invulnerable(Timer);
+
+impl Invulnerable {
+    pub fn for_secs(secs: f32) -> Self {
+        Self(Timer::from_seconds(secs, TimerMode::Once))
+    }
+
+    pub fn remaining_secs(&self) -> f32 {
+        self.0.remaining_secs()
+    }
+}
+
+/// The colour a duck had before a hit changed it, to go back to once it recovers.
+#[derive(Component, Debug)]
+struct ColorBeforeHit(Color);
+
+/// Knock `duck` back in `direction`, stun it and make it invulnerable for a while.
+pub fn react_to_hit(commands: &mut Commands, duck: Entity, direction: Vec2) {
+    commands.entity(duck).insert((
+        Velocity(direction.normalize_or_zero() * KNOCKBACK_SPEED),
+        Stunned(Timer::from_seconds(STUN_SECS, TimerMode::Once)),
+        Invulnerable::for_secs(INVULNERABLE_SECS),
+    ));
+}
+
+fn recover(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut stunned: Query<(Entity, &mut Stunned)>,
+    mut invulnerable: Query<(Entity, &mut Invulnerable)>,
+) {
+    for (entity, mut stun) in &mut stunned {
+        if stun.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Stunned>();
+        }
+    }
+    for (entity, mut invulnerability) in &mut invulnerable {
+        if invulnerability.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Invulnerable>();
+        }
+    }
+}
+
+/// Tint stunned ducks, and blink invulnerable ones. Ducks that weren't hit keep whatever
+/// colour they have, and recovered ducks get their old colour back.
+fn show_hit_ducks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut ducks: Query<(Entity, &HitState, &mut Sprite, Option<&ColorBeforeHit>)>,
+) {
+    let blink_off = (time.elapsed_secs() * BLINK_HZ * 2.0) as u32 % 2 == 1;
+    for (entity, state, mut sprite, before_hit) in &mut ducks {
+        let base = match (state.stunned || state.invulnerable, before_hit) {
+            (false, None) => continue,
+            (false, Some(before_hit)) => {
+                sprite.color = before_hit.0;
+                commands.entity(entity).remove::<ColorBeforeHit>();
+                continue;
+            }
+            (true, Some(before_hit)) => before_hit.0,
+            (true, None) => {
+                commands.entity(entity).insert(ColorBeforeHit(sprite.color));
+                sprite.color
+            }
+        };
+        let color = if state.stunned { STUNNED_TINT } else { base };
+        let alpha = if state.invulnerable && blink_off {
+            0.3
+        } else {
+            base.alpha()
+        };
+        // Only touch the sprite when the blink or tint actually changes.
+        let color = color.with_alpha(alpha);
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::{ops::RangeInclusive, time::Duration};
 
 use bevy::{
     diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
@@ -13,15 +13,34 @@ pub const PRIVATE_KEY: &[u8; bevy_renet2::netcode::NETCODE_KEY_BYTES] =
     b"an example very very secret key."; // 32-bytes
                                          // #[cfg(feature = "netcode")]
 pub const PROTOCOL_ID: u64 = 7;
+/// The port dedicated servers listen on unless told otherwise.
+pub const DEFAULT_PORT: u16 = 5000;
+/// Half the size of the play area, centered on the origin. Coins and map objects all lie
+/// inside it.
+pub const ARENA_HALF_EXTENTS: Vec2 = Vec2::new(800., 560.);
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
 #[reflect(Component)]
 pub struct Player {
     pub id: ClientId,
+    /// Coins the duck carries. A hit knocks some of them loose.
     pub score: i64,
+    /// Coins banked in the duck's nest, which are safe.
+    pub banked: i64,
     pub is_ready: bool,
 }
 
+impl Player {
+    /// What the duck has to show for itself at the end of a match.
+    pub fn total(&self) -> i64 {
+        self.score + self.banked
+    }
+}
+
+/// The room a server-side entity belongs to. Entities in different rooms never interact.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RoomId(pub u32);
+
 #[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, Component, Resource)]
 pub struct PlayerInput {
     pub up: bool,
@@ -30,25 +49,242 @@ pub struct PlayerInput {
     pub right: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Event)]
+impl PlayerInput {
+    /// The way the pressed keys point, or zero if they cancel out.
+    pub fn direction(&self) -> Vec2 {
+        let x = (self.right as i8 - self.left as i8) as f32;
+        let y = (self.up as i8 - self.down as i8) as f32;
+        Vec2::new(x, y).normalize_or_zero()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Event)]
 pub enum PlayerCommand {
-    BasicAttack,
+    /// Open a new room and join it as its host.
+    CreateRoom,
+    /// Join the room with the given join code.
+    JoinRoom {
+        code: String,
+    },
+    /// Shoot towards `aim`. It doesn't need to be normalized, but it can't be zero.
+    BasicAttack {
+        aim: [f32; 2],
+    },
     ToggleReady,
+    /// Dash the way the duck is going, if its dash is ready.
+    Dash,
+    /// Only honored when sent by the lobby host before the match starts.
+    UpdateMatchSettings(MatchSettings),
+    /// Add a bot to the room. Host only, before the match starts.
+    AddBot(BotConfig),
+    /// Change how one of the room's bots plays. Host only, before the match starts.
+    ConfigureBot {
+        id: ClientId,
+        config: BotConfig,
+    },
+    /// Take a bot out of the room. Host only, before the match starts.
+    RemoveBot {
+        id: ClientId,
+    },
+}
+
+/// Which difficulty and personality a bot plays with, by name.
+/// See [`super::bot::BotProfiles`] for what they mean.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BotConfig {
+    pub difficulty: String,
+    pub personality: String,
+}
+
+/// Match parameters the lobby host can edit before the game starts.
+/// The server keeps the authoritative copy and replicates it to every client.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Resource)]
+pub struct MatchSettings {
+    /// Length of a match in seconds.
+    pub match_length_secs: f32,
+    /// Seconds between two coin spawns.
+    pub coin_spawn_interval_secs: f32,
+    /// Maximum number of coins lying on the map at once.
+    pub max_coins: u32,
+    /// Share of coins that spawn in the map's hotspots rather than anywhere.
+    pub coin_hotspot_share: f32,
+    /// Seconds between two coin rains.
+    pub coin_rain_interval_secs: f32,
+    /// Coins dropped by each coin rain. Zero means it never rains.
+    pub coin_rain_coins: u32,
+    /// Projectile speed in world units per second.
+    pub projectile_speed: f32,
+    /// How far a projectile flies before it's gone.
+    pub projectile_range: f32,
+    /// How much a duck grows per coin, before the growth curve bends it.
+    pub growth_factor: f32,
+    pub growth_curve: GrowthCurve,
+    /// Ducks never grow past this many times their starting size.
+    pub max_growth_scale: f32,
+    /// How much growing slows a duck down. Zero means big ducks are as fast as small ones.
+    pub size_slowdown: f32,
+    /// Whether a duck's own projectiles can hit it, once they've flown a little. Other ducks'
+    /// projectiles always can.
+    pub self_hits: bool,
+    /// Whether ducks push each other around, or walk straight through each other.
+    pub duck_collisions: bool,
+    /// Shortest time between two shots from the same duck.
+    pub fire_cooldown_secs: f32,
+    /// Shots a duck can fire before it has to reload. Zero means it never has to.
+    pub magazine_size: u32,
+    pub reload_secs: f32,
+    /// How many of the map's dirt patches are mud or ice instead.
+    pub surface_patches: u32,
+    /// Seed used to generate the map.
+    pub map_seed: u64,
+}
+
+impl MatchSettings {
+    pub const MATCH_LENGTH_SECS: RangeInclusive<f32> = 30.0..=600.0;
+    pub const COIN_SPAWN_INTERVAL_SECS: RangeInclusive<f32> = 0.2..=10.0;
+    pub const MAX_COINS: RangeInclusive<u32> = 1..=500;
+    pub const COIN_HOTSPOT_SHARE: RangeInclusive<f32> = 0.0..=1.0;
+    pub const COIN_RAIN_INTERVAL_SECS: RangeInclusive<f32> = 10.0..=300.0;
+    pub const COIN_RAIN_COINS: RangeInclusive<u32> = 0..=100;
+    pub const PROJECTILE_SPEED: RangeInclusive<f32> = 100.0..=1500.0;
+    pub const PROJECTILE_RANGE: RangeInclusive<f32> = 150.0..=3000.0;
+    pub const GROWTH_FACTOR: RangeInclusive<f32> = 0.0..=0.5;
+    pub const MAX_GROWTH_SCALE: RangeInclusive<f32> = 1.0..=5.0;
+    pub const SIZE_SLOWDOWN: RangeInclusive<f32> = 0.0..=1.0;
+    pub const FIRE_COOLDOWN_SECS: RangeInclusive<f32> = 0.1..=3.0;
+    pub const MAGAZINE_SIZE: RangeInclusive<u32> = 0..=50;
+    pub const RELOAD_SECS: RangeInclusive<f32> = 0.2..=10.0;
+    pub const SURFACE_PATCHES: RangeInclusive<u32> = 0..=8;
+
+    /// Clamp every field into its allowed range. Non-finite values fall back to the default.
+    pub fn validated(self) -> Self {
+        fn clamp_f32(value: f32, range: RangeInclusive<f32>, default: f32) -> f32 {
+            if value.is_finite() {
+                value.clamp(*range.start(), *range.end())
+            } else {
+                default
+            }
+        }
+        let default = Self::default();
+        Self {
+            match_length_secs: clamp_f32(
+                self.match_length_secs,
+                Self::MATCH_LENGTH_SECS,
+                default.match_length_secs,
+            ),
+            coin_spawn_interval_secs: clamp_f32(
+                self.coin_spawn_interval_secs,
+                Self::COIN_SPAWN_INTERVAL_SECS,
+                default.coin_spawn_interval_secs,
+            ),
+            max_coins: self
+                .max_coins
+                .clamp(*Self::MAX_COINS.start(), *Self::MAX_COINS.end()),
+            coin_hotspot_share: clamp_f32(
+                self.coin_hotspot_share,
+                Self::COIN_HOTSPOT_SHARE,
+                default.coin_hotspot_share,
+            ),
+            coin_rain_interval_secs: clamp_f32(
+                self.coin_rain_interval_secs,
+                Self::COIN_RAIN_INTERVAL_SECS,
+                default.coin_rain_interval_secs,
+            ),
+            coin_rain_coins: self
+                .coin_rain_coins
+                .clamp(*Self::COIN_RAIN_COINS.start(), *Self::COIN_RAIN_COINS.end()),
+            projectile_speed: clamp_f32(
+                self.projectile_speed,
+                Self::PROJECTILE_SPEED,
+                default.projectile_speed,
+            ),
+            projectile_range: clamp_f32(
+                self.projectile_range,
+                Self::PROJECTILE_RANGE,
+                default.projectile_range,
+            ),
+            growth_factor: clamp_f32(
+                self.growth_factor,
+                Self::GROWTH_FACTOR,
+                default.growth_factor,
+            ),
+            growth_curve: self.growth_curve,
+            max_growth_scale: clamp_f32(
+                self.max_growth_scale,
+                Self::MAX_GROWTH_SCALE,
+                default.max_growth_scale,
+            ),
+            size_slowdown: clamp_f32(
+                self.size_slowdown,
+                Self::SIZE_SLOWDOWN,
+                default.size_slowdown,
+            ),
+            self_hits: self.self_hits,
+            duck_collisions: self.duck_collisions,
+            fire_cooldown_secs: clamp_f32(
+                self.fire_cooldown_secs,
+                Self::FIRE_COOLDOWN_SECS,
+                default.fire_cooldown_secs,
+            ),
+            magazine_size: self
+                .magazine_size
+                .clamp(*Self::MAGAZINE_SIZE.start(), *Self::MAGAZINE_SIZE.end()),
+            reload_secs: clamp_f32(self.reload_secs, Self::RELOAD_SECS, default.reload_secs),
+            surface_patches: self
+                .surface_patches
+                .clamp(*Self::SURFACE_PATCHES.start(), *Self::SURFACE_PATCHES.end()),
+            map_seed: self.map_seed,
+        }
+    }
+}
+
+impl Default for MatchSettings {
+    fn default() -> Self {
+        Self {
+            match_length_secs: 180.0,
+            coin_spawn_interval_secs: 1.2,
+            max_coins: 100,
+            coin_hotspot_share: 0.5,
+            coin_rain_interval_secs: 45.0,
+            coin_rain_coins: 15,
+            projectile_speed: 500.0,
+            projectile_range: 900.0,
+            growth_factor: 0.1,
+            growth_curve: GrowthCurve::Linear,
+            max_growth_scale: 3.0,
+            size_slowdown: 0.5,
+            self_hits: false,
+            duck_collisions: true,
+            fire_cooldown_secs: 0.4,
+            magazine_size: 5,
+            reload_secs: 2.0,
+            surface_patches: 2,
+            map_seed: 0,
+        }
+    }
 }
 pub enum ClientChannel {
     Input,
     Command,
 }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ServerChannel {
     ServerMessages,
     NetworkedEntities,
 }
 
-#[derive(Debug, Default, Component)]
-pub struct Velocity(pub Vec3);
+/// How fast something is moving, in world units per second.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct Velocity(pub Vec2);
 
 #[derive(Debug, Serialize, Deserialize, Component)]
 pub enum ServerMessages {
+    RoomJoined {
+        code: String,
+    },
+    RoomJoinFailed {
+        reason: String,
+    },
     PlayerCreate {
         entity: Entity,
         id: ClientId,
@@ -56,6 +292,7 @@ pub enum ServerMessages {
         is_ready: bool,
     },
     SpawnGameObject {
+        entity: Entity,
         id: u64,
         translation: [f32; 3],
     },
@@ -70,15 +307,59 @@ pub enum ServerMessages {
     SpawnCoin {
         entity: Entity,
         translation: [f32; 3],
+        kind: CoinKind,
     },
     DespawnEntity {
         entity: Entity,
     },
+    /// `owner`'s nest, where it banks its coins.
+    SpawnNest {
+        entity: Entity,
+        owner: Entity,
+        translation: [f32; 3],
+    },
+    /// A coin rain starts in `secs` seconds.
+    CoinRainIncoming {
+        secs: f32,
+    },
+    /// Sent just before the coin is despawned.
+    CoinCollected {
+        coin: Entity,
+        by: Entity,
+    },
+    /// A duck started dashing.
+    Dashed {
+        entity: Entity,
+    },
     SetPlayerReady {
         entity: Entity,
         is_ready: bool,
     },
+    SetMatchSettings {
+        host: Option<ClientId>,
+        settings: MatchSettings,
+    },
+    /// The player with this id is a bot, playing like this.
+    SetBotConfig {
+        id: ClientId,
+        config: BotConfig,
+    },
     StartGame,
+    /// The match is over, and the room is back in its lobby. Results are best first.
+    EndGame {
+        results: Vec<MatchResult>,
+    },
+}
+
+/// How a duck did in a match.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchResult {
+    pub id: ClientId,
+    /// Coins the duck was carrying when the clock ran out.
+    pub carried: i64,
+    pub banked: i64,
+    /// What the duck is ranked by: carried and banked coins together.
+    pub total: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -87,6 +368,124 @@ pub struct NetworkedEntities {
     pub translations: Vec<[f32; 3]>,
     pub facing_directions: Vec<Option<[f32; 2]>>,
     pub score: Vec<Option<i64>>,
+    pub banked: Vec<Option<i64>>,
+    pub weapons: Vec<Option<WeaponState>>,
+    pub hit_states: Vec<Option<HitState>>,
+    /// Seconds left in the room's match, if one is running.
+    pub match_secs_left: Option<f32>,
+}
+
+/// What a duck's weapon is up to, for the HUD.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Component)]
+pub struct WeaponState {
+    /// How much of the current cooldown or reload is left, from 1 down to 0.
+    pub cooldown: f32,
+    /// Shots left before reloading, unless the magazine is unlimited.
+    pub rounds: Option<u32>,
+    pub reloading: bool,
+}
+
+/// How a duck's growth follows the coins it carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GrowthCurve {
+    /// Every coin adds the same amount.
+    #[default]
+    Linear,
+    /// Grows quickly at first, then each coin counts for less.
+    SquareRoot,
+    /// Grows quickly at first, then hardly at all.
+    Logarithmic,
+}
+
+impl GrowthCurve {
+    pub const ALL: [GrowthCurve; 3] = [
+        GrowthCurve::Linear,
+        GrowthCurve::SquareRoot,
+        GrowthCurve::Logarithmic,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GrowthCurve::Linear => "Linear",
+            GrowthCurve::SquareRoot => "Square root",
+            GrowthCurve::Logarithmic => "Logarithmic",
+        }
+    }
+
+    /// How many coins' worth of growth carrying `coins` gives.
+    pub fn apply(self, coins: f32) -> f32 {
+        let coins = coins.max(0.0);
+        match self {
+            GrowthCurve::Linear => coins,
+            GrowthCurve::SquareRoot => coins.sqrt(),
+            GrowthCurve::Logarithmic => coins.ln_1p(),
+        }
+    }
+}
+
+/// How a duck is recovering from being hit, for drawing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Component)]
+pub struct HitState {
+    /// Can't walk or shoot.
+    pub stunned: bool,
+    /// Projectiles fly straight through.
+    pub invulnerable: bool,
+}
+
+/// What a coin is worth, and how it looks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect)]
+pub enum CoinKind {
+    Coin,
+    Silver,
+    /// Rare, and announced to everyone when it shows up.
+    GoldenPad,
+}
+
+impl CoinKind {
+    /// Most valuable first.
+    pub const ALL: [CoinKind; 3] = [CoinKind::GoldenPad, CoinKind::Silver, CoinKind::Coin];
+
+    pub fn value(self) -> i64 {
+        match self {
+            CoinKind::Coin => 1,
+            CoinKind::Silver => 2,
+            CoinKind::GoldenPad => 5,
+        }
+    }
+
+    /// How often the coin director picks this kind, compared to the others.
+    pub fn spawn_weight(self) -> f32 {
+        match self {
+            CoinKind::Coin => 85.0,
+            CoinKind::Silver => 13.0,
+            CoinKind::GoldenPad => 2.0,
+        }
+    }
+
+    /// How big it is compared to a plain coin.
+    pub fn size(self) -> f32 {
+        match self {
+            CoinKind::Coin => 1.0,
+            CoinKind::Silver => 1.15,
+            CoinKind::GoldenPad => 1.35,
+        }
+    }
+
+    pub fn announced(self) -> bool {
+        self == CoinKind::GoldenPad
+    }
+
+    /// The fewest coins worth `value` in total.
+    pub fn change(mut value: i64) -> Vec<CoinKind> {
+        let mut coins = vec![];
+        for kind in Self::ALL {
+            while value >= kind.value() {
+                value -= kind.value();
+                coins.push(kind);
+            }
+        }
+        coins
+    }
 }
 
 impl From<ClientChannel> for u8 {
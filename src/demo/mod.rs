@@ -6,13 +6,25 @@
 use bevy::prelude::*;
 
 pub mod animation;
+pub mod bot;
 pub mod client;
+pub mod dash;
+pub mod director;
+pub mod growth;
+pub mod hit;
 pub mod level;
 pub mod lib;
 pub mod movement;
+pub mod nav;
+pub mod nest;
 pub mod physics;
 pub mod player;
+pub mod practice;
 pub mod projectile;
+pub mod registry;
+pub mod server;
+pub mod transport;
+pub mod weapon;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((
@@ -23,5 +35,10 @@ pub(super) fn plugin(app: &mut App) {
         player::plugin,
         level::plugin,
         projectile::plugin,
+        practice::plugin,
+        weapon::plugin,
+        nest::plugin,
+        hit::plugin,
+        dash::plugin,
     ));
 }
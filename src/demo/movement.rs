@@ -6,28 +6,40 @@
 //! - Set [`MovementController`] intent based on directional keyboard input.
 //!   This is done in the `player` module, as it is specific to the player
 //!   character.
-//! - Apply movement based on [`MovementController`] intent and maximum speed.
+//! - Speed up towards [`MovementController`] intent and maximum speed, and slow down when
+//!   there's no intent, more or less quickly depending on the [`Surface`] underfoot.
+//! - Apply movement based on the resulting [`Velocity`], sliding along whatever is in the way.
+//!   A [`Dash`] overrides all that for a moment.
 //! - Wrap the character within the window.
 //!
+//! Only the server moves ducks this way. Client ducks have no [`MovementController`] or
+//! [`Velocity`], and just follow the positions the server sends.
+//!
 //! Note that the implementation used here is limited for demonstration
 //! purposes. If you want to move the player in a smoother way,
 //! consider using a [fixed timestep](https://github.com/bevyengine/bevy/blob/main/examples/movement/physics_in_fixed_timestep.rs).
 
 use bevy::{prelude::*, window::PrimaryWindow};
 
-use crate::{
-    screens::{gameplay::ScoreEvent, Screen},
-    AppSet,
-};
+use crate::{screens::Screen, AppSet};
 
 use super::{
-    lib::Player,
-    physics::{check_collision, Collider},
-    player::Coin,
+    lib::{Player, RoomId, Velocity},
+    physics::{
+        check_collision, update_spatial_hash, Collider, CollisionLayers, Contacts, Mass,
+        SpatialHash, BROADPHASE_SLACK,
+    },
 };
 
 pub fn plugin(app: &mut App) {
-    app.register_type::<(MovementController, ScreenWrap)>();
+    app.register_type::<(MovementController, Dash, Surface, ScreenWrap)>();
+    // Not just during gameplay, so the broadphase hears about everything despawned meanwhile.
+    app.add_systems(
+        Update,
+        update_spatial_hash
+            .in_set(AppSet::Update)
+            .before(apply_movement),
+    );
     app.add_systems(
         Update,
         (apply_movement, apply_screen_wrap)
@@ -37,6 +49,9 @@ pub fn plugin(app: &mut App) {
     );
 }
 
+/// Ducks this close to the middle of a mud or ice patch are standing in it.
+pub const SURFACE_RADIUS: f32 = 80.0;
+
 /// These are the movement parameters for our character controller.
 /// For now, this is only used for a single player, but it could power NPCs or
 /// other players as well.
@@ -50,6 +65,12 @@ pub struct MovementController {
     /// 1 world unit = 1 pixel when using the default 2D camera and no physics
     /// engine.
     pub max_speed: f32,
+
+    /// How quickly the character speeds up, in world units per second squared.
+    pub acceleration: f32,
+
+    /// How quickly the character comes to a stop once there's no intent.
+    pub deceleration: f32,
 }
 
 impl Default for MovementController {
@@ -58,82 +79,228 @@ impl Default for MovementController {
             intent: Vec2::ZERO,
             // 400 pixels per second is a nice default, but we can still vary this per character.
             max_speed: 400.0,
+            // Up to full speed, or back to a stop, in a fraction of a second.
+            acceleration: 3200.0,
+            deceleration: 4000.0,
         }
     }
 }
 
+impl MovementController {
+    /// The velocity after another `delta_secs` of moving as intended from `velocity`, on
+    /// `surface` (or plain ground).
+    pub fn accelerate(&self, velocity: Vec2, surface: Option<Surface>, delta_secs: f32) -> Vec2 {
+        let (speed, grip) = surface.map_or((1.0, 1.0), |surface| (surface.speed(), surface.grip()));
+        let target = self.intent * self.max_speed * speed;
+        let rate = if self.intent == Vec2::ZERO {
+            self.deceleration
+        } else {
+            self.acceleration
+        };
+        velocity.move_towards(target, rate * grip * delta_secs)
+    }
+}
+
+/// A burst of speed in a fixed direction that covers a fixed distance, whatever the intent,
+/// surface or frame rate. Walls still stop it.
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+pub struct Dash {
+    velocity: Vec2,
+    timer: Timer,
+}
+
+impl Dash {
+    pub fn new(direction: Vec2, distance: f32, secs: f32) -> Self {
+        Self {
+            velocity: direction.normalize_or_zero() * distance / secs,
+            timer: Timer::from_seconds(secs, TimerMode::Once),
+        }
+    }
+}
+
+/// Ground that changes how ducks move on it. Anywhere else is plain ground.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub enum Surface {
+    /// Slow going, but easy to stop in.
+    Mud,
+    /// A little faster, but very hard to get going, turn or stop on.
+    Ice,
+}
+
+impl Surface {
+    pub const ALL: [Surface; 2] = [Surface::Mud, Surface::Ice];
+
+    /// Map object id of a patch of this surface.
+    pub fn game_object_id(self) -> u64 {
+        match self {
+            Surface::Mud => 7,
+            Surface::Ice => 8,
+        }
+    }
+
+    pub fn from_game_object_id(id: u64) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|surface| surface.game_object_id() == id)
+    }
+
+    /// Tints the dirt patch sprite the patch is drawn with.
+    pub fn tint(self) -> Color {
+        match self {
+            Surface::Mud => Color::srgb(0.55, 0.4, 0.3),
+            Surface::Ice => Color::srgb(0.7, 0.9, 1.0),
+        }
+    }
+
+    /// Multiplies the top speed.
+    pub fn speed(self) -> f32 {
+        match self {
+            Surface::Mud => 0.55,
+            Surface::Ice => 1.15,
+        }
+    }
+
+    /// Multiplies acceleration and deceleration.
+    pub fn grip(self) -> f32 {
+        match self {
+            Surface::Mud => 1.5,
+            Surface::Ice => 0.12,
+        }
+    }
+}
+
+/// The surface a mover in `room` is standing on at `position`, if it isn't plain ground.
+fn surface_under(
+    position: Vec2,
+    room: Option<RoomId>,
+    surfaces: &Query<(&Transform, &Surface, Option<&RoomId>)>,
+) -> Option<Surface> {
+    surfaces
+        .iter()
+        .find(|(transform, _, patch_room)| {
+            patch_room.copied() == room
+                && transform.translation.xy().distance(position) < SURFACE_RADIUS
+        })
+        .map(|(_, surface, _)| *surface)
+}
+
+#[allow(clippy::type_complexity)]
 pub fn apply_movement(
     mut commands: Commands,
     time: Res<Time>,
-    mut score_event: EventWriter<ScoreEvent>,
-    mut movement_query: Query<(Entity, &MovementController)>,
-    mut colliders: Query<(Entity, &mut Transform, &Collider, Option<&Coin>)>,
+    spatial_hash: Res<SpatialHash>,
+    mut contacts: ResMut<Contacts>,
+    mut movement_query: Query<(
+        Entity,
+        &MovementController,
+        &mut Velocity,
+        Option<&mut Dash>,
+    )>,
+    // Surfaces never get in the way, and leaving them out lets `surfaces` read their transforms.
+    mut colliders: Query<
+        (
+            Entity,
+            &mut Transform,
+            &Collider,
+            Option<&RoomId>,
+            Has<Mass>,
+        ),
+        Without<Surface>,
+    >,
+    surfaces: Query<(&Transform, &Surface, Option<&RoomId>)>,
 ) {
     let mut movement_data: Vec<_> = vec![];
-    for (entity, controller) in &mut movement_query {
-        let velocity = controller.max_speed * controller.intent;
-        let movement_this_frame = velocity.extend(0.0) * time.delta_secs();
-        let (_, t, c, _) = colliders.get(entity).unwrap();
-        movement_data.push((entity, t.clone(), c.clone(), movement_this_frame));
+    for (entity, controller, mut velocity, dash) in &mut movement_query {
+        let Ok((_, t, c, room, _)) = colliders.get(entity) else {
+            continue;
+        };
+        let movement_this_frame = if let Some(mut dash) = dash {
+            // Stop exactly at the end of the dash, so it always covers the same distance.
+            let secs = dash.timer.remaining_secs().min(time.delta_secs());
+            velocity.0 = dash.velocity;
+            if dash.timer.tick(time.delta()).finished() {
+                commands.entity(entity).remove::<Dash>();
+                // Carry on at walking pace.
+                velocity.0 = dash.velocity.clamp_length_max(controller.max_speed);
+            }
+            dash.velocity.extend(0.0) * secs
+        } else {
+            let surface = surface_under(t.translation.xy(), room.copied(), &surfaces);
+            velocity.0 = controller.accelerate(velocity.0, surface, time.delta_secs());
+            velocity.0.extend(0.0) * time.delta_secs()
+        };
+        movement_data.push((
+            entity,
+            t.clone(),
+            c.clone(),
+            room.copied(),
+            movement_this_frame,
+        ));
         // println!("num movers: {:?}", movement_data.len());
     }
 
-    'outer: for (entity, mover_transform, mover_collider, movement_this_frame) in movement_data {
+    for (entity, mover_transform, mover_collider, mover_room, movement_this_frame) in movement_data
+    {
         let mut mover_mask = Vec3::ONE;
-        for (collider_entity, collider_transform, collider, maybe_coin) in colliders.iter_mut() {
+        let mut touching = vec![];
+        // Rooms are separate worlds, and only nearby colliders can get in the way.
+        let nearby = spatial_hash.query(
+            mover_room,
+            mover_transform.translation.xy() - Vec2::new(0., 10.),
+            mover_collider.size() + movement_this_frame.xy().abs() * 2.0 + BROADPHASE_SLACK,
+        );
+        for collider_entity in nearby {
             if collider_entity == entity {
                 // Don't check collision with self.
                 continue;
             }
-            if collider.collides_with_player
-                && check_collision(
-                    &(mover_transform.translation - Vec3::new(0., 10., 0.)
-                        + movement_this_frame * Vec3::new(1., 0., 1.)),
-                    &mover_collider,
-                    &collider_transform.translation,
-                    collider,
-                )
-            {
-                if maybe_coin.is_some() {
-                    score_event.send(ScoreEvent {
-                        player: entity,
-                        delta: 1,
-                    });
-                    commands.entity(collider_entity).despawn();
-                    continue;
-                } else {
-                    mover_mask.x = 0.;
-                }
+            let Ok((_, collider_transform, collider, _, has_mass)) = colliders.get(collider_entity)
+            else {
+                continue;
+            };
+            if !mover_collider.interacts_with(collider) {
+                continue;
             }
+            let hit_x = check_collision(
+                &(mover_transform.translation - Vec3::new(0., 10., 0.)
+                    + movement_this_frame * Vec3::new(1., 0., 1.)),
+                &mover_collider,
+                &collider_transform.translation,
+                collider,
+            );
+            let hit_y = check_collision(
+                &(mover_transform.translation - Vec3::new(0., 10., 0.)
+                    + movement_this_frame * Vec3::new(0., 1., 1.)),
+                &mover_collider,
+                &collider_transform.translation,
+                collider,
+            );
+            if !hit_x && !hit_y {
+                continue;
+            }
+            touching.push((collider_entity, collider.layers));
 
-            if collider.collides_with_player
-                && check_collision(
-                    &(mover_transform.translation - Vec3::new(0., 10., 0.)
-                        + movement_this_frame * Vec3::new(0., 1., 1.)),
-                    &mover_collider,
-                    &collider_transform.translation,
-                    collider,
-                )
-            {
-                if maybe_coin.is_some() {
-                    score_event.send(ScoreEvent {
-                        player: entity,
-                        delta: 1,
-                    });
-                    commands.entity(collider_entity).despawn();
-                    continue;
-                } else {
+            // Pickups, triggers and hazards don't get in the way. What touching them does is
+            // up to whoever observes the collision events. Other ducks get pushed out of the
+            // way afterwards.
+            if collider.layers.intersects(CollisionLayers::SOLID) && !has_mass {
+                if hit_x {
+                    mover_mask.x = 0.;
+                }
+                if hit_y {
                     mover_mask.y = 0.;
                 }
             }
-
-            // No movement possible.
-            if mover_mask == Vec3::ZERO {
-                continue 'outer;
-            }
         }
+        contacts.report(&mut commands, entity, &touching);
         let mut transform = colliders.get_mut(entity).unwrap().1;
         transform.translation += movement_this_frame * mover_mask;
+        // Running into a wall stops you in that direction, so you slide along it.
+        if let Ok((_, _, mut velocity, _)) = movement_query.get_mut(entity) {
+            velocity.0 *= mover_mask.xy();
+        }
     }
     // for (entity, controller) in movement_query.iter_mut() {
     //     let velocity = controller.max_speed * controller.intent;
@@ -0,0 +1,341 @@
+//! Navigation for bots: a grid of walkable cells built from a room's static colliders,
+//! and A* path queries over it.
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+};
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+
+use super::{
+    bot::Bot,
+    lib::{Player, RoomId, ARENA_HALF_EXTENTS},
+    physics::{Collider, CollisionLayers},
+    server::{generate_world, ServerGameObject, ServerLobby},
+};
+
+const CELL_SIZE: f32 = 20.0;
+/// Obstacles are grown by this much, so ducks following a path don't clip corners.
+const CLEARANCE: f32 = 10.0;
+/// Ducks collide with their feet, which sit this far below their position.
+/// See [`super::movement::apply_movement`].
+const FEET_OFFSET: Vec2 = Vec2::new(0., 10.);
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Update, build_nav_grids.after(generate_world));
+}
+
+/// Walkable cells over a room's map.
+#[derive(Debug, Clone)]
+pub struct NavGrid {
+    origin: Vec2,
+    width: usize,
+    height: usize,
+    blocked: Vec<bool>,
+}
+
+impl NavGrid {
+    /// Build a grid in which every cell overlapping one of the obstacles, given as
+    /// `(center, size)`, is blocked.
+    pub fn new(obstacles: impl IntoIterator<Item = (Vec2, Vec2)>) -> Self {
+        let width = (ARENA_HALF_EXTENTS.x * 2.0 / CELL_SIZE).ceil() as usize;
+        let height = (ARENA_HALF_EXTENTS.y * 2.0 / CELL_SIZE).ceil() as usize;
+        let mut grid = Self {
+            origin: -ARENA_HALF_EXTENTS,
+            width,
+            height,
+            blocked: vec![false; width * height],
+        };
+        for (center, size) in obstacles {
+            // Positions in the grid are duck positions, so move obstacles up to meet their feet.
+            let center = center + FEET_OFFSET;
+            let half_size = size / 2.0 + CLEARANCE;
+            let (Some(min), Some(max)) = (
+                grid.clamped_cell(center - half_size),
+                grid.clamped_cell(center + half_size),
+            ) else {
+                continue;
+            };
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    let index = grid.index(UVec2::new(x, y));
+                    grid.blocked[index] = true;
+                }
+            }
+        }
+        grid
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        CELL_SIZE
+    }
+
+    pub fn cell(&self, position: Vec2) -> Option<UVec2> {
+        let cell = ((position - self.origin) / CELL_SIZE).floor();
+        (cell.x >= 0.0
+            && cell.y >= 0.0
+            && (cell.x as usize) < self.width
+            && (cell.y as usize) < self.height)
+            .then(|| cell.as_uvec2())
+    }
+
+    /// Like [`NavGrid::cell`], but positions outside the grid snap to its edge.
+    /// Returns `None` only if the grid is empty.
+    fn clamped_cell(&self, position: Vec2) -> Option<UVec2> {
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+        let cell = ((position - self.origin) / CELL_SIZE).floor();
+        Some(UVec2::new(
+            cell.x.clamp(0.0, (self.width - 1) as f32) as u32,
+            cell.y.clamp(0.0, (self.height - 1) as f32) as u32,
+        ))
+    }
+
+    pub fn center(&self, cell: UVec2) -> Vec2 {
+        self.origin + (cell.as_vec2() + 0.5) * CELL_SIZE
+    }
+
+    pub fn is_walkable(&self, cell: UVec2) -> bool {
+        !self.blocked[self.index(cell)]
+    }
+
+    fn index(&self, cell: UVec2) -> usize {
+        cell.y as usize * self.width + cell.x as usize
+    }
+
+    /// The walkable cells next to `cell`, with `goal` always counting as walkable.
+    /// Diagonal moves can't cut past a blocked corner.
+    fn neighbours(&self, cell: UVec2, goal: UVec2) -> impl Iterator<Item = (UVec2, f32)> + '_ {
+        const STEPS: [IVec2; 8] = [
+            IVec2::new(1, 0),
+            IVec2::new(-1, 0),
+            IVec2::new(0, 1),
+            IVec2::new(0, -1),
+            IVec2::new(1, 1),
+            IVec2::new(1, -1),
+            IVec2::new(-1, 1),
+            IVec2::new(-1, -1),
+        ];
+        let walkable = move |cell: IVec2| {
+            cell.x >= 0
+                && cell.y >= 0
+                && (cell.x as usize) < self.width
+                && (cell.y as usize) < self.height
+                && (cell.as_uvec2() == goal || self.is_walkable(cell.as_uvec2()))
+        };
+        let from = cell.as_ivec2();
+        STEPS.into_iter().filter_map(move |step| {
+            let to = from + step;
+            let diagonal = step.x != 0 && step.y != 0;
+            let open = walkable(to)
+                && (!diagonal
+                    || (walkable(from + IVec2::new(step.x, 0))
+                        && walkable(from + IVec2::new(0, step.y))));
+            open.then(|| {
+                (
+                    to.as_uvec2(),
+                    if diagonal {
+                        std::f32::consts::SQRT_2
+                    } else {
+                        1.0
+                    },
+                )
+            })
+        })
+    }
+
+    /// Find a path from `start` to `goal` with A*. The path is a list of cell centers,
+    /// ending at `goal` itself. Returns `None` if the goal can't be reached.
+    ///
+    /// The start and goal cells are always treated as walkable, since ducks and coins
+    /// can end up pressed against an obstacle.
+    /// Positions outside the grid are treated as the nearest cell on its edge.
+    pub fn find_path(&self, start: Vec2, goal: Vec2) -> Option<Vec<Vec2>> {
+        let start_cell = self.clamped_cell(start)?;
+        let goal_cell = self.clamped_cell(goal)?;
+
+        let heuristic = |cell: UVec2| {
+            // Octile distance: the exact cost on an empty 8-connected grid.
+            let delta = (cell.as_ivec2() - goal_cell.as_ivec2()).abs().as_vec2();
+            delta.max_element() + (std::f32::consts::SQRT_2 - 1.0) * delta.min_element()
+        };
+
+        let mut open = BinaryHeap::new();
+        let mut came_from: HashMap<UVec2, UVec2> = HashMap::new();
+        let mut cost: HashMap<UVec2, f32> = HashMap::from([(start_cell, 0.0)]);
+        open.push(OpenCell {
+            cell: start_cell,
+            estimate: heuristic(start_cell),
+        });
+
+        while let Some(OpenCell { cell, .. }) = open.pop() {
+            if cell == goal_cell {
+                let mut path = vec![goal];
+                let mut current = cell;
+                while let Some(previous) = came_from.get(&current) {
+                    if *previous != start_cell {
+                        path.push(self.center(*previous));
+                    }
+                    current = *previous;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            let cell_cost = cost[&cell];
+            for (next, step_cost) in self.neighbours(cell, goal_cell) {
+                let next_cost = cell_cost + step_cost;
+                if cost.get(&next).is_some_and(|known| *known <= next_cost) {
+                    continue;
+                }
+                cost.insert(next, next_cost);
+                came_from.insert(next, cell);
+                open.push(OpenCell {
+                    cell: next,
+                    estimate: next_cost + heuristic(next),
+                });
+            }
+        }
+        None
+    }
+}
+
+/// A cell waiting to be explored, ordered so the [`BinaryHeap`] pops the cheapest first.
+#[derive(Debug, PartialEq)]
+struct OpenCell {
+    cell: UVec2,
+    estimate: f32,
+}
+
+impl Eq for OpenCell {}
+
+impl Ord for OpenCell {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for OpenCell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Build the grid for rooms whose map was just (re)generated.
+fn build_nav_grids(
+    mut lobby: ResMut<ServerLobby>,
+    obstacles: Query<(&Transform, &Collider, &RoomId), With<ServerGameObject>>,
+) {
+    for (room_id, room) in lobby.rooms.iter_mut() {
+        if room.nav.is_some() || room.map_seed.is_none() {
+            continue;
+        }
+        room.nav = Some(NavGrid::new(
+            obstacles
+                .iter()
+                .filter(|(_, collider, obstacle_room)| {
+                    obstacle_room == room_id && collider.mask.intersects(CollisionLayers::PLAYER)
+                })
+                .map(|(transform, collider, _)| (transform.translation.xy(), collider.size())),
+        ));
+    }
+}
+
+/// Which room's grid the server window shows, if any.
+#[derive(Debug, Default, Resource)]
+struct NavDebug {
+    enabled: bool,
+    room: usize,
+}
+
+/// A debug overlay of the navigation grid and bot paths for the dedicated server's window.
+/// Press F2 to toggle it and Tab to cycle through rooms.
+pub fn debug_plugin(app: &mut App) {
+    app.init_resource::<NavDebug>();
+    app.add_systems(Startup, spawn_debug_camera);
+    app.add_systems(
+        Update,
+        (
+            toggle_nav_debug.run_if(input_just_pressed(KeyCode::F2)),
+            cycle_nav_debug_room.run_if(input_just_pressed(KeyCode::Tab)),
+            draw_nav_debug,
+        )
+            .chain(),
+    );
+}
+
+fn spawn_debug_camera(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Debug Camera"),
+        Camera2d,
+        // Fit the whole map into the window.
+        OrthographicProjection {
+            scale: 1.6,
+            ..OrthographicProjection::default_2d()
+        },
+    ));
+}
+
+fn toggle_nav_debug(mut debug: ResMut<NavDebug>) {
+    debug.enabled = !debug.enabled;
+}
+
+fn cycle_nav_debug_room(mut debug: ResMut<NavDebug>) {
+    debug.room += 1;
+}
+
+fn draw_nav_debug(
+    mut gizmos: Gizmos,
+    debug: Res<NavDebug>,
+    lobby: Res<ServerLobby>,
+    ducks: Query<(&Transform, &RoomId, Option<&Bot>), With<Player>>,
+) {
+    if !debug.enabled || lobby.rooms.is_empty() {
+        return;
+    }
+    let mut room_ids: Vec<_> = lobby.rooms.keys().copied().collect();
+    room_ids.sort_by_key(|room_id| room_id.0);
+    let room_id = room_ids[debug.room % room_ids.len()];
+    let Some(grid) = lobby.rooms[&room_id].nav.as_ref() else {
+        return;
+    };
+
+    gizmos.grid_2d(
+        Isometry2d::IDENTITY,
+        UVec2::new(grid.width as u32, grid.height as u32),
+        Vec2::splat(CELL_SIZE),
+        Color::srgba(1.0, 1.0, 1.0, 0.05),
+    );
+    for y in 0..grid.height as u32 {
+        for x in 0..grid.width as u32 {
+            let cell = UVec2::new(x, y);
+            if !grid.is_walkable(cell) {
+                gizmos.rect_2d(
+                    Isometry2d::from_translation(grid.center(cell)),
+                    Vec2::splat(CELL_SIZE),
+                    Color::srgba(0.9, 0.2, 0.2, 0.6),
+                );
+            }
+        }
+    }
+
+    for (transform, duck_room, bot) in &ducks {
+        if *duck_room != room_id {
+            continue;
+        }
+        let position = transform.translation.xy();
+        let color = if bot.is_some() {
+            Color::srgb(0.9, 0.8, 0.2)
+        } else {
+            Color::srgb(0.2, 0.8, 0.9)
+        };
+        gizmos.circle_2d(Isometry2d::from_translation(position), 12.0, color);
+        if let Some(bot) = bot.filter(|bot| !bot.path.is_empty()) {
+            gizmos.linestrip_2d(
+                std::iter::once(position).chain(bot.path.iter().copied()),
+                color,
+            );
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Every duck has a nest at its spawn point. Once it has left the nest, coming back and
+//! standing in it for a few seconds banks the coins it carries, and banked coins can't be
+//! knocked loose by a hit.
+
+use bevy::prelude::*;
+
+use crate::screens::{gameplay::ScoreEvent, Screen};
+
+use super::{
+    client::ControlledPlayer,
+    lib::{Player, RoomId, ServerChannel, ServerMessages},
+    physics::{Collider, CollisionEnded, CollisionLayers, TriggerEntered},
+    server::Room,
+    transport::ServerOutbox,
+};
+
+pub const NEST_RADIUS: f32 = 48.0;
+/// How long a duck has to stay in its nest for its coins to be banked.
+const BANK_SECS: f32 = 3.0;
+const NEST_COLOR: Color = Color::srgb(0.6, 0.45, 0.25);
+const OWN_NEST_COLOR: Color = Color::srgb(1.0, 0.85, 0.3);
+
+/// Client side: draw the nests.
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Update, draw_nests.run_if(in_state(Screen::Gameplay)));
+}
+
+/// Server side: bank coins, and clear away the nests of ducks that left.
+pub(super) fn server_plugin(app: &mut App) {
+    app.add_observer(start_banking);
+    app.add_observer(stop_banking);
+    app.add_observer(despawn_orphaned_nests);
+    app.add_systems(Update, bank_coins);
+}
+
+/// A duck's nest. Only its owner can bank coins in it.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Nest {
+    pub owner: Entity,
+}
+
+/// A duck standing in its own nest, counting down to banking what it carries.
+#[derive(Component, Debug)]
+struct Banking(Timer);
+
+/// A duck that has left its nest at least once. Ducks spawn inside their nests, and
+/// shouldn't start banking before they've even moved.
+#[derive(Component, Debug)]
+struct LeftNest;
+
+/// Spawn `owner`'s nest and tell the clients in its room about it.
+pub fn spawn_nest(
+    commands: &mut Commands,
+    outbox: &mut ServerOutbox,
+    room: &Room,
+    owner: Entity,
+    position: Vec2,
+) -> Entity {
+    let translation = position.extend(1.);
+    let entity = commands
+        .spawn((
+            Name::new("Nest"),
+            Nest { owner },
+            Transform::from_translation(translation),
+            Collider::circle(
+                NEST_RADIUS,
+                CollisionLayers::TRIGGER,
+                CollisionLayers::PLAYER,
+            ),
+            room.id,
+        ))
+        .id();
+    room.send(
+        outbox,
+        ServerChannel::ServerMessages,
+        &ServerMessages::SpawnNest {
+            entity,
+            owner,
+            translation: translation.into(),
+        },
+    );
+    entity
+}
+
+fn start_banking(
+    trigger: Trigger<TriggerEntered>,
+    mut commands: Commands,
+    nests: Query<&Nest>,
+    left_nest: Query<(), With<LeftNest>>,
+) {
+    let event = trigger.event();
+    if left_nest.contains(event.entity)
+        && nests
+            .get(event.trigger)
+            .is_ok_and(|nest| nest.owner == event.entity)
+    {
+        commands
+            .entity(event.entity)
+            .insert(Banking(Timer::from_seconds(
+                BANK_SECS,
+                TimerMode::Repeating,
+            )));
+    }
+}
+
+fn stop_banking(trigger: Trigger<CollisionEnded>, mut commands: Commands, nests: Query<&Nest>) {
+    let event = trigger.event();
+    if nests
+        .get(event.other)
+        .is_ok_and(|nest| nest.owner == event.entity)
+    {
+        if let Some(mut duck) = commands.get_entity(event.entity) {
+            duck.remove::<Banking>().insert(LeftNest);
+        }
+    }
+}
+
+/// Move everything a duck carries into its bank, every few seconds it spends in its nest.
+fn bank_coins(
+    time: Res<Time>,
+    mut ducks: Query<(Entity, &mut Banking, &mut Player)>,
+    mut score_event: EventWriter<ScoreEvent>,
+) {
+    for (entity, mut banking, mut player) in &mut ducks {
+        if !banking.0.tick(time.delta()).just_finished() || player.score <= 0 {
+            continue;
+        }
+        println!("Player {} banked {} coins", player.id, player.score);
+        player.banked += player.score;
+        // Carrying fewer coins shrinks the duck, which `handle_score_event` takes care of.
+        score_event.send(ScoreEvent {
+            player: entity,
+            delta: -player.score,
+        });
+    }
+}
+
+fn despawn_orphaned_nests(
+    trigger: Trigger<OnRemove, Player>,
+    mut commands: Commands,
+    nests: Query<(Entity, &Nest), With<RoomId>>,
+) {
+    for (entity, nest) in &nests {
+        if nest.owner == trigger.entity() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn draw_nests(
+    mut gizmos: Gizmos,
+    nests: Query<(&Transform, &Nest)>,
+    controlled: Query<(), With<ControlledPlayer>>,
+) {
+    for (transform, nest) in &nests {
+        let color = if controlled.contains(nest.owner) {
+            OWN_NEST_COLOR
+        } else {
+            NEST_COLOR
+        };
+        gizmos.circle_2d(
+            Isometry2d::from_translation(transform.translation.xy()),
+            NEST_RADIUS,
+            color,
+        );
+    }
+}
@@ -1,24 +1,381 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 
-pub(super) fn plugin(_app: &mut App) {
-    // No setup required for this plugin.
-    // It's still good to have a function here so that we can add some setup
-    // later if needed.
+use super::lib::RoomId;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SpatialHash>();
+    app.init_resource::<Contacts>();
+    app.add_observer(forget_contacts);
+}
+
+/// Two colliders started touching. `entity` is the one that moved into `other`.
+///
+/// Sent with [`Commands::trigger`], so react to it with an observer.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct CollisionStarted {
+    pub entity: Entity,
+    pub other: Entity,
+}
+
+/// Two colliders stopped touching. Either may already be despawned.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct CollisionEnded {
+    pub entity: Entity,
+    pub other: Entity,
+}
+
+/// `entity` moved into a collider on the [`CollisionLayers::TRIGGER`] layer. Sent instead of
+/// [`CollisionStarted`]; leaving it again is a [`CollisionEnded`].
+#[derive(Debug, Clone, Copy, Event)]
+pub struct TriggerEntered {
+    pub trigger: Entity,
+    pub entity: Entity,
+}
+
+/// Bitmask of collision layers. A [`Collider`] is a member of some layers and has a mask of
+/// the layers it interacts with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CollisionLayers(pub u32);
+
+impl CollisionLayers {
+    pub const NONE: Self = Self(0);
+    pub const PLAYER: Self = Self(1 << 0);
+    pub const PROJECTILE: Self = Self(1 << 1);
+    /// Map objects like trees, walls and the pond.
+    pub const STATIC: Self = Self(1 << 2);
+    /// Things that get picked up instead of getting in the way, like coins.
+    pub const PICKUP: Self = Self(1 << 3);
+    /// Areas that notice things entering them but don't get in the way.
+    pub const TRIGGER: Self = Self(1 << 4);
+    pub const HAZARD: Self = Self(1 << 5);
+    pub const ALL: Self = Self(u32::MAX);
+
+    /// Layers that stop a moving duck. Anything with a [`Mass`] gets pushed out of the way
+    /// instead.
+    pub const SOLID: Self = Self(Self::PLAYER.0 | Self::STATIC.0);
+
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for CollisionLayers {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    /// An axis-aligned box with this width and height.
+    Aabb(Vec2),
+    Circle(f32),
 }
 
 #[derive(Debug, Clone, Component)]
 pub struct Collider {
-    pub size: Vec2,
-    pub collides_with_player: bool,
-    pub collides_with_projectile: bool,
+    pub shape: Shape,
+    /// Layers this collider is in.
+    pub layers: CollisionLayers,
+    /// Layers this collider interacts with.
+    pub mask: CollisionLayers,
+}
+
+impl Collider {
+    pub fn aabb(size: Vec2, layers: CollisionLayers, mask: CollisionLayers) -> Self {
+        Self {
+            shape: Shape::Aabb(size),
+            layers,
+            mask,
+        }
+    }
+
+    pub fn circle(radius: f32, layers: CollisionLayers, mask: CollisionLayers) -> Self {
+        Self {
+            shape: Shape::Circle(radius),
+            layers,
+            mask,
+        }
+    }
+
+    /// Width and height of the box around the shape.
+    pub fn size(&self) -> Vec2 {
+        match self.shape {
+            Shape::Aabb(size) => size,
+            Shape::Circle(radius) => Vec2::splat(radius * 2.0),
+        }
+    }
+
+    /// Whether the two colliders should be tested against each other at all. Both have to
+    /// want it: a pond stops ducks, but ducks' bullets fly over it.
+    pub fn interacts_with(&self, other: &Collider) -> bool {
+        self.mask.intersects(other.layers) && other.mask.intersects(self.layers)
+    }
 }
 
 // fn to check if two entities are colliding
 pub fn check_collision(a: &Vec3, a_collider: &Collider, b: &Vec3, b_collider: &Collider) -> bool {
-    let a_min = a.truncate() - a_collider.size / 2.0;
-    let a_max = a.truncate() + a_collider.size / 2.0;
-    let b_min = b.truncate() - b_collider.size / 2.0;
-    let b_max = b.truncate() + b_collider.size / 2.0;
+    let a = a.truncate();
+    let b = b.truncate();
+    match (a_collider.shape, b_collider.shape) {
+        (Shape::Aabb(a_size), Shape::Aabb(b_size)) => {
+            let a_min = a - a_size / 2.0;
+            let a_max = a + a_size / 2.0;
+            let b_min = b - b_size / 2.0;
+            let b_max = b + b_size / 2.0;
+
+            a_min.x < b_max.x && a_max.x > b_min.x && a_min.y < b_max.y && a_max.y > b_min.y
+        }
+        (Shape::Circle(a_radius), Shape::Circle(b_radius)) => {
+            a.distance_squared(b) < (a_radius + b_radius).powi(2)
+        }
+        (Shape::Aabb(size), Shape::Circle(radius)) => box_overlaps_circle(a, size, b, radius),
+        (Shape::Circle(radius), Shape::Aabb(size)) => box_overlaps_circle(b, size, a, radius),
+    }
+}
+
+fn box_overlaps_circle(center: Vec2, size: Vec2, circle: Vec2, radius: f32) -> bool {
+    let closest = circle.clamp(center - size / 2.0, center + size / 2.0);
+    closest.distance_squared(circle) < radius * radius
+}
+
+/// Sweep a collider from `start` along `motion` and find when it first touches a static one,
+/// as a fraction of `motion` between 0 and 1. Colliders that already overlap at the start hit
+/// at 0. Two circles are swept exactly; anything involving a box sweeps the boxes around both.
+///
+/// Unlike [`check_collision`] at the end position, this catches fast movers that would
+/// otherwise skip over thin walls and small ducks in a single step.
+pub fn sweep_collision(
+    start: Vec2,
+    motion: Vec2,
+    collider: &Collider,
+    other: Vec2,
+    other_collider: &Collider,
+) -> Option<f32> {
+    if let (Shape::Circle(radius), Shape::Circle(other_radius)) =
+        (collider.shape, other_collider.shape)
+    {
+        return sweep_circles(start, motion, other, radius + other_radius);
+    }
+
+    // Grow the other box by our size, and cast a ray from our center against it.
+    let half_size = (collider.size() + other_collider.size()) / 2.0;
+    let min = other - half_size;
+    let max = other + half_size;
+
+    let mut enter = 0.0_f32;
+    let mut exit = 1.0_f32;
+    for axis in 0..2 {
+        if motion[axis] == 0.0 {
+            // Never moves along this axis, so it has to be overlapping on it already.
+            if start[axis] <= min[axis] || start[axis] >= max[axis] {
+                return None;
+            }
+            continue;
+        }
+        let near = (min[axis] - start[axis]) / motion[axis];
+        let far = (max[axis] - start[axis]) / motion[axis];
+        enter = enter.max(near.min(far));
+        exit = exit.min(near.max(far));
+        // Just touching edges doesn't count, like in `check_collision`.
+        if enter >= exit {
+            return None;
+        }
+    }
+    Some(enter)
+}
+
+/// Cast a ray from `start` against a circle of `radius` around `other`.
+fn sweep_circles(start: Vec2, motion: Vec2, other: Vec2, radius: f32) -> Option<f32> {
+    let offset = start - other;
+    let c = offset.length_squared() - radius * radius;
+    if c < 0.0 {
+        return Some(0.0);
+    }
+    let a = motion.length_squared();
+    let b = offset.dot(motion);
+    // Standing still outside, or moving away.
+    if a == 0.0 || b >= 0.0 {
+        return None;
+    }
+    let discriminant = b * b - a * c;
+    // Missing it, or only grazing it.
+    if discriminant <= 0.0 {
+        return None;
+    }
+    let time = (-b - discriminant.sqrt()) / a;
+    (time < 1.0).then_some(time)
+}
+
+/// How hard something is to push around. Two things with mass that overlap are pushed
+/// apart, the lighter one further.
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub struct Mass(pub f32);
+
+/// How far to move two overlapping circles to separate them, sharing the distance in
+/// inverse proportion to their masses. `None` if they don't overlap.
+///
+/// Swapping the two circles swaps the pushes, unless they're exactly on top of each other.
+/// Then `a` goes left and `b` right, so callers should pick which is which consistently.
+pub fn push_apart(
+    (a, a_radius, a_mass): (Vec2, f32, f32),
+    (b, b_radius, b_mass): (Vec2, f32, f32),
+) -> Option<(Vec2, Vec2)> {
+    let offset = b - a;
+    let overlap = a_radius + b_radius - offset.length();
+    if overlap <= 0.0 {
+        return None;
+    }
+    let normal = offset.try_normalize().unwrap_or(Vec2::X);
+    let total_mass = a_mass + b_mass;
+    let (a_share, b_share) = if total_mass > 0.0 {
+        (b_mass / total_mass, a_mass / total_mass)
+    } else {
+        (0.5, 0.5)
+    };
+    Some((-normal * overlap * a_share, normal * overlap * b_share))
+}
+
+/// Width and height of a [`SpatialHash`] cell. About two ducks wide.
+const SPATIAL_HASH_CELL_SIZE: f32 = 64.0;
+/// Other colliders may have moved a little since the [`SpatialHash`] was brought up to date,
+/// so look a bit further than we're going.
+pub const BROADPHASE_SLACK: f32 = 16.0;
+
+/// A broadphase for collision queries: every collider, bucketed by room and by the grid
+/// cells its box covers, so a query only looks at what's nearby instead of everything.
+///
+/// [`update_spatial_hash`] keeps it up to date with colliders that moved, appeared or went
+/// away. It runs once a frame, in `Update` before anything moves, and nowhere else, so
+/// `FixedUpdate` systems see the hash as of the last frame. Coins and map objects never move,
+/// so keeping it up to date costs next to nothing. Queries only return candidates; callers
+/// still do their own exact test, and look [`BROADPHASE_SLACK`] further for anything that
+/// may have moved since.
+#[derive(Debug, Default, Resource)]
+pub struct SpatialHash {
+    cells: HashMap<(Option<RoomId>, IVec2), Vec<(Entity, Rect)>>,
+    /// Where each entity is in `cells`, so it can be taken out again.
+    entries: HashMap<Entity, (Option<RoomId>, Rect)>,
+}
+
+impl SpatialHash {
+    /// Add an entity, or move it if it's already in here.
+    pub fn insert(&mut self, entity: Entity, room: Option<RoomId>, center: Vec2, size: Vec2) {
+        let rect = Rect::from_center_size(center, size);
+        if self.entries.get(&entity) == Some(&(room, rect)) {
+            return;
+        }
+        self.remove(entity);
+        for cell in Self::cells_covering(rect) {
+            self.cells
+                .entry((room, cell))
+                .or_default()
+                .push((entity, rect));
+        }
+        self.entries.insert(entity, (room, rect));
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        let Some((room, rect)) = self.entries.remove(&entity) else {
+            return;
+        };
+        for cell in Self::cells_covering(rect) {
+            if let Some(entries) = self.cells.get_mut(&(room, cell)) {
+                entries.retain(|(other, _)| *other != entity);
+            }
+        }
+    }
+
+    /// Every entity in `room` whose box overlaps the box at `center` with `size`.
+    pub fn query(&self, room: Option<RoomId>, center: Vec2, size: Vec2) -> Vec<Entity> {
+        let rect = Rect::from_center_size(center, size);
+        let mut found: Vec<Entity> = Self::cells_covering(rect)
+            .filter_map(|cell| self.cells.get(&(room, cell)))
+            .flatten()
+            .filter(|(_, other)| !other.intersect(rect).is_empty())
+            .map(|(entity, _)| *entity)
+            .collect();
+        // Boxes spanning several cells show up once per cell.
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    fn cells_covering(rect: Rect) -> impl Iterator<Item = IVec2> {
+        let min = (rect.min / SPATIAL_HASH_CELL_SIZE).floor().as_ivec2();
+        let max = (rect.max / SPATIAL_HASH_CELL_SIZE).floor().as_ivec2();
+        (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+    }
+}
+
+/// Bring the [`SpatialHash`] up to date with every [`Collider`] that changed since last time.
+pub fn update_spatial_hash(
+    mut spatial_hash: ResMut<SpatialHash>,
+    colliders: Query<
+        (Entity, &Transform, &Collider, Option<&RoomId>),
+        Or<(Changed<Transform>, Changed<Collider>, Changed<RoomId>)>,
+    >,
+    mut removed: RemovedComponents<Collider>,
+) {
+    for entity in removed.read() {
+        spatial_hash.remove(entity);
+    }
+    for (entity, transform, collider, room) in &colliders {
+        spatial_hash.insert(
+            entity,
+            room.copied(),
+            transform.translation.xy(),
+            collider.size(),
+        );
+    }
+}
+
+/// What each moving collider touched after its last move, so the collision events only fire
+/// when that changes.
+#[derive(Debug, Default, Resource)]
+pub struct Contacts(HashMap<Entity, Vec<Entity>>);
+
+impl Contacts {
+    /// Record everything `entity` touches after a move, with the layers each is in, and send
+    /// the collision events for whatever it started or stopped touching.
+    pub fn report(
+        &mut self,
+        commands: &mut Commands,
+        entity: Entity,
+        touching: &[(Entity, CollisionLayers)],
+    ) {
+        let previous = self.0.remove(&entity).unwrap_or_default();
+        for &(other, layers) in touching {
+            if previous.contains(&other) {
+                continue;
+            }
+            if layers.intersects(CollisionLayers::TRIGGER) {
+                commands.trigger(TriggerEntered {
+                    trigger: other,
+                    entity,
+                });
+            } else {
+                commands.trigger(CollisionStarted { entity, other });
+            }
+        }
+        for other in previous {
+            if !touching.iter().any(|(touched, _)| *touched == other) {
+                commands.trigger(CollisionEnded { entity, other });
+            }
+        }
+        if !touching.is_empty() {
+            self.0
+                .insert(entity, touching.iter().map(|(other, _)| *other).collect());
+        }
+    }
+}
 
-    a_min.x < b_max.x && a_max.x > b_min.x && a_min.y < b_max.y && a_max.y > b_min.y
+fn forget_contacts(trigger: Trigger<OnRemove, Collider>, mut contacts: ResMut<Contacts>) {
+    contacts.0.remove(&trigger.entity());
 }
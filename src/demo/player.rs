@@ -9,11 +9,19 @@ use bevy::{
 };
 
 use crate::{
-    asset_tracking::LoadResource, demo::animation::PlayerAnimation, screens::Screen, AppSet,
+    asset_tracking::LoadResource,
+    demo::{animation::PlayerAnimation, lib::CoinKind, physics::CollisionStarted},
+    screens::Screen,
+    AppSet,
 };
 
+/// How long a collected coin takes to fly into the duck that got it.
+const COIN_PICKUP_SECS: f32 = 0.25;
+
 pub(super) fn plugin(app: &mut App) {
     app.load_resource::<PlayerAssets>();
+    app.add_observer(spawn_coin_pickup);
+    app.add_systems(Update, animate_coin_pickups.in_set(AppSet::Update));
 
     // Record directional input as movement controls.
     // app.add_systems(
@@ -26,6 +34,104 @@ pub(super) fn plugin(app: &mut App) {
 #[reflect(Component)]
 pub struct Coin {
     pub claimed_by: Option<Entity>,
+    pub kind: CoinKind,
+}
+
+/// Ducks claim coins they walk into. When several reach the same coin in one tick, the
+/// closest one wins, then the lowest entity, so it doesn't depend on who moved first.
+/// The server hands out claimed coins afterwards.
+pub fn claim_coins(
+    trigger: Trigger<CollisionStarted>,
+    mut coins: Query<(&mut Coin, &Transform)>,
+    ducks: Query<&Transform>,
+) {
+    let collision = trigger.event();
+    let Ok((mut coin, coin_transform)) = coins.get_mut(collision.other) else {
+        return;
+    };
+    let Ok(duck_transform) = ducks.get(collision.entity) else {
+        return;
+    };
+    let distance = |transform: &Transform| {
+        transform
+            .translation
+            .xy()
+            .distance_squared(coin_transform.translation.xy())
+    };
+    let wins = match coin
+        .claimed_by
+        .and_then(|other| Some((other, ducks.get(other).ok()?)))
+    {
+        Some((other, other_transform)) => {
+            (distance(duck_transform), collision.entity) < (distance(other_transform), other)
+        }
+        None => true,
+    };
+    if wins {
+        coin.claimed_by = Some(collision.entity);
+    }
+}
+
+/// The server says `by` collected `coin`, both local entities.
+#[derive(Debug, Event)]
+pub struct CollectCoin {
+    pub coin: Entity,
+    pub by: Entity,
+}
+
+/// A copy of a collected coin, flying into the duck that got it.
+#[derive(Component, Debug)]
+struct CoinPickup {
+    by: Entity,
+    from: Vec3,
+    scale: Vec3,
+    timer: Timer,
+}
+
+fn spawn_coin_pickup(
+    trigger: Trigger<CollectCoin>,
+    mut commands: Commands,
+    coins: Query<(&Transform, &Sprite)>,
+) {
+    let Ok((transform, sprite)) = coins.get(trigger.event().coin) else {
+        return;
+    };
+    commands.spawn((
+        Name::new("Coin Pickup"),
+        sprite.clone(),
+        *transform,
+        CoinPickup {
+            by: trigger.event().by,
+            from: transform.translation,
+            scale: transform.scale,
+            timer: Timer::from_seconds(COIN_PICKUP_SECS, TimerMode::Once),
+        },
+        StateScoped(Screen::Gameplay),
+    ));
+}
+
+/// Shrink into the duck, then disappear.
+fn animate_coin_pickups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pickups: Query<(Entity, &mut CoinPickup, &mut Transform)>,
+    ducks: Query<&Transform, Without<CoinPickup>>,
+) {
+    for (entity, mut pickup, mut transform) in &mut pickups {
+        pickup.timer.tick(time.delta());
+        let Ok(duck) = ducks.get(pickup.by) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        if pickup.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let progress = pickup.timer.fraction();
+        let z = transform.translation.z;
+        transform.translation = pickup.from.lerp(duck.translation, progress).with_z(z);
+        transform.scale = pickup.scale * (1.0 - progress);
+    }
 }
 
 /// A command to spawn the player character.
@@ -107,6 +213,10 @@ pub struct PlayerAssets {
     #[dependency]
     pub coin: Handle<Image>,
     #[dependency]
+    pub silver_coin: Handle<Image>,
+    #[dependency]
+    pub golden_pad: Handle<Image>,
+    #[dependency]
     pub dirt_patch: Handle<Image>,
     #[dependency]
     pub map: Handle<Image>,
@@ -126,6 +236,8 @@ pub struct PlayerAssets {
     pub wall_v_large: Handle<Image>,
     #[dependency]
     pub steps: Vec<Handle<AudioSource>>,
+    #[dependency]
+    pub dash: Handle<AudioSource>,
 }
 
 impl PlayerAssets {
@@ -136,6 +248,8 @@ impl PlayerAssets {
     pub const PATH_WALL_V_LARGE: &'static str = "images/wall_v_large.png";
     pub const PATH_WALL_H_LARGE: &'static str = "images/wall_h_large.png";
     pub const PATH_COIN: &'static str = "images/coin.png";
+    pub const PATH_SILVER_COIN: &'static str = "images/silver_coin.png";
+    pub const PATH_GOLDEN_PAD: &'static str = "images/golden_pad.png";
     pub const PATH_DIRT_PATCH: &'static str = "images/dirt_patch.png";
     pub const PATH_MAP: &'static str = "images/map.png";
     pub const PATH_POND: &'static str = "images/pond.png";
@@ -144,6 +258,15 @@ impl PlayerAssets {
     pub const PATH_STEP_2: &'static str = "audio/sound_effects/step2.ogg";
     pub const PATH_STEP_3: &'static str = "audio/sound_effects/step3.ogg";
     pub const PATH_STEP_4: &'static str = "audio/sound_effects/step4.ogg";
+    pub const PATH_DASH: &'static str = "audio/sound_effects/dash.wav";
+
+    pub fn coin_image(&self, kind: CoinKind) -> Handle<Image> {
+        match kind {
+            CoinKind::Coin => self.coin.clone(),
+            CoinKind::Silver => self.silver_coin.clone(),
+            CoinKind::GoldenPad => self.golden_pad.clone(),
+        }
+    }
 }
 
 impl FromWorld for PlayerAssets {
@@ -163,6 +286,8 @@ impl FromWorld for PlayerAssets {
             wall_v_large: assets.load(PlayerAssets::PATH_WALL_V_LARGE),
             bullet: assets.load(PlayerAssets::PATH_BULLET),
             coin: assets.load(PlayerAssets::PATH_COIN),
+            silver_coin: assets.load(PlayerAssets::PATH_SILVER_COIN),
+            golden_pad: assets.load(PlayerAssets::PATH_GOLDEN_PAD),
             dirt_patch: assets.load(PlayerAssets::PATH_DIRT_PATCH),
             map: assets.load(PlayerAssets::PATH_MAP),
             pond: assets.load(PlayerAssets::PATH_POND),
@@ -173,6 +298,7 @@ impl FromWorld for PlayerAssets {
                 assets.load(PlayerAssets::PATH_STEP_3),
                 assets.load(PlayerAssets::PATH_STEP_4),
             ],
+            dash: assets.load(PlayerAssets::PATH_DASH),
         }
     }
 }
@@ -0,0 +1,131 @@
+//! Offline practice: the authoritative game rules running in-process against bots,
+//! with no `RenetServer` or `RenetClient` involved. It lasts until the player goes back to
+//! the title screen, so matches can be replayed from the lobby.
+
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use bevy_renet2::prelude::ClientId;
+
+use crate::screens::Screen;
+
+use super::{
+    bot::{Bot, BotProfiles},
+    client::{disconnect, CurrentClientId},
+    lib::PlayerCommand,
+    server::{self, spawn_bot, BotId, ServerLobby},
+    transport::{ClientCommand, ClientInbox, ClientInput, ClientOutbox, ServerOutbox},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        update_practice.run_if(resource_exists::<PracticeSession>),
+    );
+    app.add_systems(
+        OnEnter(Screen::Title),
+        end_practice.run_if(resource_exists::<PracticeSession>),
+    );
+}
+
+/// The client id of the local player in practice. Bots count up from zero, so this never clashes.
+const PRACTICE_CLIENT_ID: ClientId = ClientId::MAX;
+const PRACTICE_BOTS: usize = 3;
+
+/// Present while an offline practice match is running.
+#[derive(Resource, Debug)]
+pub struct PracticeSession;
+
+/// The simulation for a practice match, kept in its own world so its entities never mix
+/// with the client's.
+struct PracticeApp(App);
+
+/// Start a practice match: a private room with a few easy bots. The player can change them
+/// in the lobby, and the match starts once they're ready.
+pub fn start_practice(world: &mut World) {
+    // An online session would keep feeding the same inbox.
+    world
+        .run_system_once(disconnect)
+        .expect("disconnecting should always work");
+    *world.resource_mut::<ClientInbox>() = ClientInbox::default();
+
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, server::plugin));
+    app.finish();
+    app.cleanup();
+
+    app.world_mut().send_event(ClientCommand {
+        client_id: PRACTICE_CLIENT_ID,
+        command: PlayerCommand::CreateRoom,
+    });
+    app.update();
+    app.world_mut()
+        .run_system_once(add_practice_bots)
+        .expect("the practice room should exist");
+
+    world.insert_resource(CurrentClientId(PRACTICE_CLIENT_ID));
+    world.insert_resource(PracticeSession);
+    world.insert_non_send_resource(PracticeApp(app));
+}
+
+fn add_practice_bots(
+    mut commands: Commands,
+    mut lobby: ResMut<ServerLobby>,
+    mut outbox: ResMut<ServerOutbox>,
+    mut bot_id: ResMut<BotId>,
+    profiles: Res<BotProfiles>,
+) {
+    // Start out gentle, with a mix of personalities.
+    let difficulty = &profiles.difficulties[0];
+    for room in lobby.rooms.values_mut() {
+        for personality in profiles.personalities.iter().cycle().take(PRACTICE_BOTS) {
+            let bot = Bot::new(difficulty.clone(), personality.clone());
+            spawn_bot(&mut commands, &mut outbox, room, &mut bot_id, bot);
+        }
+    }
+}
+
+/// Hand the local player's input to the simulation, step it, and deliver what it said.
+fn update_practice(world: &mut World) {
+    let (input, commands) = {
+        let mut outbox = world.resource_mut::<ClientOutbox>();
+        (outbox.input.take(), std::mem::take(&mut outbox.commands))
+    };
+    let Some(mut practice) = world.get_non_send_resource_mut::<PracticeApp>() else {
+        return;
+    };
+
+    let sim = practice.0.world_mut();
+    if let Some(input) = input {
+        sim.send_event(ClientInput {
+            client_id: PRACTICE_CLIENT_ID,
+            input,
+        });
+    }
+    for command in commands {
+        sim.send_event(ClientCommand {
+            client_id: PRACTICE_CLIENT_ID,
+            command,
+        });
+    }
+    practice.0.update();
+
+    // The local player is the only one listening, so every message is for us.
+    let messages: Vec<_> = practice
+        .0
+        .world_mut()
+        .resource_mut::<ServerOutbox>()
+        .drain()
+        .collect();
+    let mut inbox = world.resource_mut::<ClientInbox>();
+    for message in messages {
+        inbox.push(message.channel, message.payload);
+    }
+}
+
+/// Stop the simulation. The client forgets the room and its [`CurrentClientId`] on the title
+/// screen, like after any other session.
+fn end_practice(mut commands: Commands) {
+    commands.remove_resource::<PracticeSession>();
+    commands.queue(|world: &mut World| {
+        world.remove_non_send_resource::<PracticeApp>();
+    });
+}
@@ -1,14 +1,28 @@
 use bevy::prelude::*;
 
-use crate::AppSet;
+use crate::{demo::player::PlayerAssets, screens::Screen, AppSet};
+
+/// How long the pop plays when a projectile disappears.
+const POP_SECS: f32 = 0.2;
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<Projectile>();
 
     // Record directional input as movement controls.
     app.add_systems(Update, handle_move_projectiles.in_set(AppSet::Update));
+
+    app.add_systems(Update, animate_pops.in_set(AppSet::Update));
+    app.add_observer(pop_on_despawn);
 }
 
+/// A projectile sprite that pops when the server despawns it, whether it hit something or
+/// ran out of range.
+#[derive(Component, Debug)]
+pub struct PopOnDespawn;
+
+#[derive(Component, Debug)]
+struct Pop(Timer);
+
 #[derive(Component, Debug, Clone, Copy, Default, Reflect)]
 #[reflect(Component)]
 pub struct Projectile {
@@ -51,3 +65,43 @@ fn handle_move_projectiles(time: Res<Time>, mut query: Query<(&Projectile, &mut
             projectile.direction.extend(0.0) * projectile.speed * time.delta_secs();
     }
 }
+
+fn pop_on_despawn(
+    trigger: Trigger<OnRemove, PopOnDespawn>,
+    mut commands: Commands,
+    transforms: Query<&Transform>,
+    player_assets: Res<PlayerAssets>,
+) {
+    let Ok(transform) = transforms.get(trigger.entity()) else {
+        return;
+    };
+    commands.spawn((
+        Name::new("Projectile Pop"),
+        Sprite {
+            image: player_assets.bullet.clone(),
+            custom_size: Some(Vec2::new(12., 18.)),
+            ..default()
+        },
+        *transform,
+        Pop(Timer::from_seconds(POP_SECS, TimerMode::Once)),
+        StateScoped(Screen::Gameplay),
+    ));
+}
+
+/// Grow and fade out, then disappear.
+fn animate_pops(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pops: Query<(Entity, &mut Pop, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut pop, mut transform, mut sprite) in &mut pops {
+        pop.0.tick(time.delta());
+        if pop.0.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let progress = pop.0.fraction();
+        transform.scale = Vec3::splat(1.0 + progress);
+        sprite.color = sprite.color.with_alpha(1.0 - progress);
+    }
+}
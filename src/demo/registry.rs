@@ -0,0 +1,53 @@
+//! Types shared by the server registry, the dedicated servers that register with it,
+//! and the server browser on the client.
+
+use std::{fmt, net::SocketAddr, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+/// Where the registry listens when run locally.
+pub const DEFAULT_REGISTRY_ADDR: &str = "127.0.0.1:5100";
+/// The registry the server browser asks for servers.
+pub const DEFAULT_REGISTRY_URL: &str = "http://127.0.0.1:5100";
+/// How often dedicated servers send a heartbeat to the registry.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+/// UDP port servers listen on for LAN discovery broadcasts.
+pub const DISCOVERY_PORT: u16 = 5098;
+/// What clients broadcast to find servers on the local network.
+pub const DISCOVERY_REQUEST: &[u8] = b"duck-battles/discover";
+/// Servers that miss heartbeats for this long are dropped from the registry.
+pub const LISTING_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// What a dedicated server reports about itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerInfo {
+    pub name: String,
+    /// Address clients connect to.
+    pub native_addr: SocketAddr,
+    /// Address of the server's HTTP info endpoint, used to measure ping.
+    pub http_addr: SocketAddr,
+    pub players: usize,
+    pub max_players: usize,
+    pub map: String,
+    pub state: ServerState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ServerState {
+    /// No rooms are open.
+    Empty,
+    /// At least one room is waiting for players.
+    Lobby,
+    /// Every room is in a match.
+    InGame,
+}
+
+impl fmt::Display for ServerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerState::Empty => write!(f, "Empty"),
+            ServerState::Lobby => write!(f, "In lobby"),
+            ServerState::InGame => write!(f, "In game"),
+        }
+    }
+}
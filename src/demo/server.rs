@@ -0,0 +1,1498 @@
+//! The authoritative game simulation: rooms, players, bots, projectiles, coins and the map.
+//!
+//! It doesn't know about the network. Clients reach it through [`ClientCommand`],
+//! [`ClientInput`] and [`ClientLeft`] events, and everything it has to say ends up in the
+//! [`ServerOutbox`]. The dedicated server pipes those through renet, while offline practice
+//! runs the simulation in the same process as the client.
+
+use std::{cmp::Reverse, collections::HashMap, f32::consts::PI};
+
+use bevy::prelude::*;
+use bevy_renet2::prelude::ClientId;
+use rand::{rngs::StdRng, seq::index::sample, Rng, SeedableRng};
+
+use crate::{
+    demo::{
+        animation::FacingDirection,
+        bot::{self, Bot, BotProfiles},
+        client::{coin_collider, game_object_collider, player_collider, projectile_collider},
+        dash::{self, DashRequest},
+        director::{self, CoinDirector, Hotspot},
+        growth::Growth,
+        hit::{self, react_to_hit, Invulnerable, Stunned},
+        lib::{
+            CoinKind, HitState, MatchResult, MatchSettings, NetworkedEntities, Player,
+            PlayerCommand, PlayerInput, RoomId, ServerChannel, ServerMessages, Velocity,
+            ARENA_HALF_EXTENTS,
+        },
+        movement::{apply_movement, apply_screen_wrap, MovementController, Surface},
+        nav::{self, NavGrid},
+        nest::{self, spawn_nest, Nest},
+        physics::{
+            self, check_collision, push_apart, sweep_collision, update_spatial_hash, Collider,
+            CollisionLayers, CollisionStarted, Contacts, Mass, SpatialHash, BROADPHASE_SLACK,
+        },
+        player::{claim_coins, Coin},
+        registry::ServerState,
+        transport::{ClientCommand, ClientInput, ClientLeft, Recipient, ServerOutbox},
+        weapon::{tick_weapons, Weapon},
+    },
+    screens::gameplay::{MatchClock, ScoreEvent},
+    AppSet,
+};
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<ServerLobby>();
+    app.init_resource::<BotId>();
+    app.init_resource::<ServerOutbox>();
+    app.add_event::<ClientCommand>();
+    app.add_event::<ClientInput>();
+    app.add_event::<ClientLeft>();
+    app.add_event::<ScoreEvent>();
+    app.add_event::<AttackRequest>();
+    app.add_plugins((
+        physics::plugin,
+        bot::plugin,
+        nav::plugin,
+        director::plugin,
+        nest::server_plugin,
+        hit::server_plugin,
+        dash::server_plugin,
+    ));
+
+    app.add_systems(
+        Update,
+        (
+            (
+                handle_client_left,
+                handle_client_commands,
+                handle_client_inputs,
+                tick_weapons,
+                handle_attack_requests,
+            )
+                .chain(),
+            server_network_sync,
+            move_players_system,
+            generate_world,
+            handle_score_event,
+        ),
+    );
+    // Matches end before anything else runs, so nothing this frame still acts on the ducks
+    // `reset_room` replaces.
+    app.add_systems(PreUpdate, update_match_clock);
+    // The only place the spatial hash is brought up to date; `FixedUpdate` reads it as is.
+    app.add_systems(
+        Update,
+        (
+            update_spatial_hash,
+            apply_movement,
+            push_ducks,
+            resolve_coin_claims,
+            apply_screen_wrap,
+        )
+            .chain()
+            .in_set(AppSet::Update),
+    );
+    app.add_systems(FixedUpdate, move_projectiles);
+
+    app.add_observer(projectile_hit);
+    app.add_observer(claim_coins);
+    app.add_observer(despawn_on_removal::<Projectile>);
+    app.add_observer(despawn_on_removal::<Coin>);
+    app.add_observer(despawn_on_removal::<ServerGameObject>);
+    app.add_observer(despawn_on_removal::<Nest>);
+}
+
+#[derive(Component)]
+pub struct ServerGameObject(pub u64);
+
+/// Every room hosted by this server, and which room each connected client is in.
+#[derive(Debug, Default, Resource)]
+pub struct ServerLobby {
+    pub rooms: HashMap<RoomId, Room>,
+    pub clients: HashMap<ClientId, RoomId>,
+    next_room_id: u32,
+}
+
+impl ServerLobby {
+    /// Characters used for join codes. Leaves out look-alikes such as `0`/`O` and `1`/`I`.
+    const CODE_ALPHABET: &'static [u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+    const CODE_LENGTH: usize = 4;
+
+    pub fn create_room(&mut self) -> RoomId {
+        let code = loop {
+            let code: String = (0..Self::CODE_LENGTH)
+                .map(|_| Self::CODE_ALPHABET[fastrand::usize(..Self::CODE_ALPHABET.len())] as char)
+                .collect();
+            if self.find_room(&code).is_none() {
+                break code;
+            }
+        };
+        let room_id = RoomId(self.next_room_id);
+        self.next_room_id += 1;
+        println!("Created room {} ({:?})", code, room_id);
+        self.rooms.insert(room_id, Room::new(room_id, code));
+        room_id
+    }
+
+    pub fn find_room(&self, code: &str) -> Option<RoomId> {
+        let code = code.trim().to_uppercase();
+        self.rooms
+            .iter()
+            .find(|(_, room)| room.code == code)
+            .map(|(room_id, _)| *room_id)
+    }
+
+    pub fn room_of(&self, client_id: ClientId) -> Option<RoomId> {
+        self.clients.get(&client_id).copied()
+    }
+
+    /// The room `client_id` hosts, as long as its match hasn't started yet.
+    fn hosted_lobby_mut(&mut self, client_id: ClientId) -> Option<&mut Room> {
+        let room_id = self.room_of(client_id)?;
+        self.rooms
+            .get_mut(&room_id)
+            .filter(|room| room.host == Some(client_id) && !room.in_game())
+    }
+
+    pub fn state(&self) -> ServerState {
+        if self.rooms.is_empty() {
+            ServerState::Empty
+        } else if self.rooms.values().any(|room| !room.in_game()) {
+            ServerState::Lobby
+        } else {
+            ServerState::InGame
+        }
+    }
+}
+
+/// An independent match with its own lobby, map, settings and clock.
+#[derive(Debug)]
+pub struct Room {
+    pub id: RoomId,
+    /// Short code other players enter to join this room.
+    pub code: String,
+    /// Every duck in the room, bots included.
+    pub players: HashMap<ClientId, Entity>,
+    /// Which of the [`SPAWN_POSITIONS`] each duck spawned at, which is also where its nest is.
+    spawn_slots: HashMap<ClientId, usize>,
+    /// The client allowed to edit the [`MatchSettings`].
+    pub host: Option<ClientId>,
+    pub settings: MatchSettings,
+    /// The seed the current map was generated with, if any.
+    pub map_seed: Option<u64>,
+    /// How many mud and ice patches the current map has.
+    pub surface_patches: u32,
+    /// Where bots can walk on the current map. Rebuilt whenever the map is.
+    pub nav: Option<NavGrid>,
+    /// Only set while a match is running.
+    pub clock: Option<MatchClock>,
+    pub coin_director: CoinDirector,
+}
+
+impl Room {
+    fn new(id: RoomId, code: String) -> Self {
+        Self {
+            id,
+            code,
+            players: HashMap::new(),
+            spawn_slots: HashMap::new(),
+            host: None,
+            settings: MatchSettings {
+                map_seed: fastrand::u64(..),
+                ..default()
+            },
+            map_seed: None,
+            surface_patches: 0,
+            nav: None,
+            clock: None,
+            coin_director: CoinDirector::default(),
+        }
+    }
+
+    pub fn in_game(&self) -> bool {
+        self.clock.is_some()
+    }
+
+    /// Claim the first spawn point no other duck in the room has. Once they're all taken,
+    /// ducks have to share.
+    fn claim_spawn_slot(&mut self, client_id: ClientId) -> usize {
+        let slot = (0..SPAWN_POSITIONS.len())
+            .find(|slot| !self.spawn_slots.values().any(|taken| taken == slot))
+            .unwrap_or(self.spawn_slots.len() % SPAWN_POSITIONS.len());
+        self.spawn_slots.insert(client_id, slot);
+        slot
+    }
+
+    /// Take a duck out of the room, freeing its spawn point. Despawning it is up to the caller.
+    fn remove_player(&mut self, client_id: ClientId) -> Option<Entity> {
+        self.spawn_slots.remove(&client_id);
+        self.players.remove(&client_id)
+    }
+
+    /// Send a message to every client in this room.
+    pub fn send(
+        &self,
+        outbox: &mut ServerOutbox,
+        channel: ServerChannel,
+        message: &ServerMessages,
+    ) {
+        outbox.send(Recipient::Room(self.id), channel, message);
+    }
+
+    pub fn send_settings(&self, outbox: &mut ServerOutbox) {
+        self.send(
+            outbox,
+            ServerChannel::ServerMessages,
+            &ServerMessages::SetMatchSettings {
+                host: self.host,
+                settings: self.settings,
+            },
+        );
+    }
+}
+
+const PROJECTILE_LIFETIME_SECS: f32 = 5.0;
+/// How far a projectile flies before it can hit the duck that fired it, so a duck can't shoot
+/// itself just by firing.
+const PROJECTILE_ARMING_DISTANCE: f32 = 60.0;
+/// Projectiles this far outside the arena can't come back to hit anything.
+const ARENA_MARGIN: f32 = 100.0;
+const SPAWN_POSITIONS: [Vec2; 8] = [
+    Vec2::new(-250., 0.),
+    Vec2::new(250., 0.),
+    Vec2::new(0., 250.),
+    Vec2::new(0., -250.),
+    Vec2::new(176., 176.),
+    Vec2::new(-176., 176.),
+    Vec2::new(-176., -176.),
+    Vec2::new(176., -176.),
+];
+
+/// A duck wants to shoot in a direction. Humans and bots both go through this.
+#[derive(Debug, Event)]
+pub struct AttackRequest {
+    pub shooter: Entity,
+    pub direction: Vec2,
+}
+
+/// The next client id handed out to a bot.
+#[derive(Debug, Default, Resource)]
+pub struct BotId(u64);
+
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct Projectile {
+    pub speed: f32,
+    pub direction: Vec2,
+    pub owner: Entity,
+    /// How much further it can fly before it's gone.
+    pub range_left: f32,
+    /// Seconds until it's gone, in case it never gets far enough.
+    pub lifetime_left: f32,
+    /// How much further it has to fly before it can hit its owner.
+    pub arming_left: f32,
+}
+
+fn handle_client_left(
+    mut events: EventReader<ClientLeft>,
+    mut commands: Commands,
+    mut lobby: ResMut<ServerLobby>,
+    mut outbox: ResMut<ServerOutbox>,
+    room_entities: Query<(Entity, &RoomId)>,
+    bots: Query<(), With<Bot>>,
+) {
+    for event in events.read() {
+        leave_room(
+            &mut commands,
+            &mut outbox,
+            &mut lobby,
+            &room_entities,
+            &bots,
+            event.client_id,
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_client_commands(
+    mut events: EventReader<ClientCommand>,
+    mut commands: Commands,
+    mut lobby: ResMut<ServerLobby>,
+    mut outbox: ResMut<ServerOutbox>,
+    mut players: Query<(Entity, &mut Player, &Transform, &RoomId)>,
+    game_objects: Query<(Entity, &Transform, &ServerGameObject, &RoomId)>,
+    nests: Query<(Entity, &Transform, &Nest, &RoomId)>,
+    mut bots: Query<&mut Bot>,
+    profiles: Res<BotProfiles>,
+    mut bot_id: ResMut<BotId>,
+    mut attacks: EventWriter<AttackRequest>,
+    mut dashes: EventWriter<DashRequest>,
+) {
+    for ClientCommand { client_id, command } in events.read() {
+        let client_id = *client_id;
+        match command {
+            PlayerCommand::CreateRoom => {
+                if lobby.room_of(client_id).is_some() {
+                    println!("Client {} is already in a room", client_id);
+                    continue;
+                }
+                let room_id = lobby.create_room();
+                join_room(
+                    &mut commands,
+                    &mut outbox,
+                    &mut lobby,
+                    &players,
+                    &game_objects,
+                    &nests,
+                    &bots,
+                    client_id,
+                    room_id,
+                );
+            }
+            PlayerCommand::JoinRoom { code } => {
+                if lobby.room_of(client_id).is_some() {
+                    println!("Client {} is already in a room", client_id);
+                    continue;
+                }
+                let reason = match lobby.find_room(code) {
+                    Some(room_id) if !lobby.rooms[&room_id].in_game() => {
+                        join_room(
+                            &mut commands,
+                            &mut outbox,
+                            &mut lobby,
+                            &players,
+                            &game_objects,
+                            &nests,
+                            &bots,
+                            client_id,
+                            room_id,
+                        );
+                        continue;
+                    }
+                    Some(_) => "That match has already started.",
+                    None => "There is no room with that code.",
+                };
+                outbox.send(
+                    Recipient::Client(client_id),
+                    ServerChannel::ServerMessages,
+                    &ServerMessages::RoomJoinFailed {
+                        reason: reason.to_string(),
+                    },
+                );
+            }
+            PlayerCommand::BasicAttack { aim } => {
+                println!("Received basic attack from client {}", client_id);
+
+                let aim = Vec2::from_array(*aim);
+                let Some(direction) = aim.try_normalize() else {
+                    println!("Ignoring attack with invalid aim {:?}", aim);
+                    continue;
+                };
+                let Some(room_id) = lobby.room_of(client_id) else {
+                    continue;
+                };
+                if let Some(player_entity) = lobby.rooms[&room_id].players.get(&client_id) {
+                    attacks.send(AttackRequest {
+                        shooter: *player_entity,
+                        direction,
+                    });
+                }
+            }
+            PlayerCommand::Dash => {
+                let Some(room_id) = lobby.room_of(client_id) else {
+                    continue;
+                };
+                if let Some(player_entity) = lobby.rooms[&room_id].players.get(&client_id) {
+                    dashes.send(DashRequest {
+                        dasher: *player_entity,
+                    });
+                }
+            }
+            PlayerCommand::ToggleReady => {
+                let Some(room_id) = lobby.room_of(client_id) else {
+                    continue;
+                };
+                let room = lobby.rooms.get_mut(&room_id).unwrap();
+                if room.in_game() {
+                    continue;
+                }
+                if let Some(player_entity) = room.players.get(&client_id) {
+                    if let Ok((_, mut player, _, _)) = players.get_mut(*player_entity) {
+                        player.is_ready = !player.is_ready;
+                        println!("Player {} is now {:?}", client_id, player.is_ready);
+                        room.send(
+                            &mut outbox,
+                            ServerChannel::ServerMessages,
+                            &ServerMessages::SetPlayerReady {
+                                entity: *player_entity,
+                                is_ready: player.is_ready,
+                            },
+                        );
+                    }
+                }
+                if room.players.len() == 1 {
+                    continue;
+                }
+
+                let all_players_ready_check = room.players.values().all(|player_entity| {
+                    players
+                        .get(*player_entity)
+                        .map_or(true, |(_, player, _, _)| player.is_ready)
+                });
+
+                if all_players_ready_check {
+                    room.send(
+                        &mut outbox,
+                        ServerChannel::ServerMessages,
+                        &ServerMessages::StartGame,
+                    );
+                    room.clock = Some(MatchClock::new(&room.settings));
+                    room.coin_director.start(&room.settings);
+                }
+            }
+            PlayerCommand::UpdateMatchSettings(new_settings) => {
+                let Some(room) = lobby.hosted_lobby_mut(client_id) else {
+                    println!("Ignoring match settings from client {}", client_id);
+                    continue;
+                };
+                room.settings = new_settings.validated();
+                // Always echo the validated settings, so the host's optimistic copy is
+                // corrected if anything was clamped.
+                room.send_settings(&mut outbox);
+            }
+            PlayerCommand::AddBot(config) => {
+                let Some(room) = lobby.hosted_lobby_mut(client_id) else {
+                    println!("Ignoring bot from client {}", client_id);
+                    continue;
+                };
+                let Some(bot) = profiles.bot(config) else {
+                    println!("Unknown bot profile {:?}", config);
+                    continue;
+                };
+                spawn_bot(&mut commands, &mut outbox, room, &mut bot_id, bot);
+            }
+            PlayerCommand::ConfigureBot { id, config } => {
+                let Some(room) = lobby.hosted_lobby_mut(client_id) else {
+                    println!("Ignoring bot config from client {}", client_id);
+                    continue;
+                };
+                let Some(new_bot) = profiles.bot(config) else {
+                    println!("Unknown bot profile {:?}", config);
+                    continue;
+                };
+                let Some(mut bot) = room
+                    .players
+                    .get(id)
+                    .and_then(|entity| bots.get_mut(*entity).ok())
+                else {
+                    continue;
+                };
+                *bot = new_bot;
+                room.send(
+                    &mut outbox,
+                    ServerChannel::ServerMessages,
+                    &ServerMessages::SetBotConfig {
+                        id: *id,
+                        config: config.clone(),
+                    },
+                );
+            }
+            PlayerCommand::RemoveBot { id } => {
+                let Some(room) = lobby.hosted_lobby_mut(client_id) else {
+                    println!("Ignoring bot removal from client {}", client_id);
+                    continue;
+                };
+                let Some(entity) = room
+                    .players
+                    .get(id)
+                    .copied()
+                    .filter(|entity| bots.contains(*entity))
+                else {
+                    continue;
+                };
+                room.remove_player(*id);
+                commands.entity(entity).despawn();
+                room.send(
+                    &mut outbox,
+                    ServerChannel::ServerMessages,
+                    &ServerMessages::PlayerRemove { id: *id },
+                );
+            }
+        }
+    }
+}
+
+/// Shoot for whoever asked to, human or bot, as long as their weapon is ready.
+/// Requests that come in too fast are dropped.
+fn handle_attack_requests(
+    mut events: EventReader<AttackRequest>,
+    mut commands: Commands,
+    mut outbox: ResMut<ServerOutbox>,
+    lobby: Res<ServerLobby>,
+    mut shooters: Query<(&Transform, &RoomId, &mut Weapon, Has<Stunned>)>,
+) {
+    for event in events.read() {
+        let Ok((transform, room_id, mut weapon, stunned)) = shooters.get_mut(event.shooter) else {
+            continue;
+        };
+        if stunned {
+            continue;
+        }
+        let Some(room) = lobby.rooms.get(room_id) else {
+            continue;
+        };
+        if !weapon.ready() {
+            continue;
+        }
+        weapon.fire(&room.settings);
+        spawn_projectile(
+            &mut commands,
+            &mut outbox,
+            room,
+            event.shooter,
+            transform.translation.xy(),
+            event.direction,
+            20.0,
+        );
+    }
+}
+
+fn handle_client_inputs(
+    mut events: EventReader<ClientInput>,
+    mut commands: Commands,
+    lobby: Res<ServerLobby>,
+) {
+    for ClientInput { client_id, input } in events.read() {
+        if let Some(player_entity) = lobby
+            .room_of(*client_id)
+            .and_then(|room_id| lobby.rooms[&room_id].players.get(client_id))
+        {
+            commands.entity(*player_entity).insert(*input);
+        }
+    }
+}
+
+/// Put a client into a room: spawn their duck and catch them up on everything already in it.
+#[allow(clippy::too_many_arguments)]
+fn join_room(
+    commands: &mut Commands,
+    outbox: &mut ServerOutbox,
+    lobby: &mut ServerLobby,
+    players: &Query<(Entity, &mut Player, &Transform, &RoomId)>,
+    game_objects: &Query<(Entity, &Transform, &ServerGameObject, &RoomId)>,
+    nests: &Query<(Entity, &Transform, &Nest, &RoomId)>,
+    bots: &Query<&mut Bot>,
+    client_id: ClientId,
+    room_id: RoomId,
+) {
+    let Some(room) = lobby.rooms.get_mut(&room_id) else {
+        return;
+    };
+    println!("Player {} joined room {}.", client_id, room.code);
+
+    outbox.send(
+        Recipient::Client(client_id),
+        ServerChannel::ServerMessages,
+        &ServerMessages::RoomJoined {
+            code: room.code.clone(),
+        },
+    );
+
+    // Initialize other players for this new client
+    for (entity, player, transform, player_room) in players.iter() {
+        if *player_room != room_id {
+            continue;
+        }
+        outbox.send(
+            Recipient::Client(client_id),
+            ServerChannel::ServerMessages,
+            &ServerMessages::PlayerCreate {
+                id: player.id,
+                entity,
+                translation: transform.translation.into(),
+                is_ready: player.is_ready,
+            },
+        );
+        if let Ok(bot) = bots.get(entity) {
+            outbox.send(
+                Recipient::Client(client_id),
+                ServerChannel::ServerMessages,
+                &ServerMessages::SetBotConfig {
+                    id: player.id,
+                    config: bot.config(),
+                },
+            );
+        }
+    }
+
+    // Initialize game objects for this player
+    for (entity, transform, id, object_room) in game_objects.iter() {
+        if *object_room != room_id {
+            continue;
+        }
+        outbox.send(
+            Recipient::Client(client_id),
+            ServerChannel::ServerMessages,
+            &ServerMessages::SpawnGameObject {
+                entity,
+                id: id.0,
+                translation: transform.translation.into(),
+            },
+        );
+    }
+
+    // Nests go after the ducks, so clients know their owners.
+    for (entity, transform, nest, nest_room) in nests.iter() {
+        if *nest_room != room_id {
+            continue;
+        }
+        outbox.send(
+            Recipient::Client(client_id),
+            ServerChannel::ServerMessages,
+            &ServerMessages::SpawnNest {
+                entity,
+                owner: nest.owner,
+                translation: transform.translation.into(),
+            },
+        );
+    }
+
+    spawn_duck(commands, outbox, room, client_id, None);
+    lobby.clients.insert(client_id, room_id);
+
+    // The first player to join hosts the room.
+    if room.host.is_none() {
+        room.host = Some(client_id);
+    }
+    room.send_settings(outbox);
+}
+
+/// Spawn a duck and its nest at the room's first free spawn point, and tell the room.
+/// Humans and bots get the same duck, but bots are always ready.
+fn spawn_duck(
+    commands: &mut Commands,
+    outbox: &mut ServerOutbox,
+    room: &mut Room,
+    client_id: ClientId,
+    bot: Option<Bot>,
+) -> Entity {
+    let is_ready = bot.is_some();
+    let slot = room.claim_spawn_slot(client_id);
+    let transform = Transform::from_translation(SPAWN_POSITIONS[slot].extend(8.));
+    let growth = Growth::from_score(0, &room.settings);
+    let entity = commands
+        .spawn((
+            transform,
+            MovementController {
+                max_speed: growth.max_speed,
+                acceleration: growth.acceleration,
+                deceleration: growth.deceleration,
+                ..default()
+            },
+            room.id,
+        ))
+        .insert((player_collider(growth), Mass(growth.mass)))
+        .insert(PlayerInput::default())
+        .insert(Velocity::default())
+        .insert(Weapon::default())
+        .insert(Player {
+            id: client_id,
+            score: 0,
+            banked: 0,
+            is_ready,
+        })
+        .id();
+    room.players.insert(client_id, entity);
+
+    room.send(
+        outbox,
+        ServerChannel::ServerMessages,
+        &ServerMessages::PlayerCreate {
+            id: client_id,
+            entity,
+            translation: transform.translation.into(),
+            is_ready,
+        },
+    );
+    spawn_nest(commands, outbox, room, entity, transform.translation.xy());
+    if let Some(bot) = bot {
+        room.send(
+            outbox,
+            ServerChannel::ServerMessages,
+            &ServerMessages::SetBotConfig {
+                id: client_id,
+                config: bot.config(),
+            },
+        );
+        commands.entity(entity).insert(bot);
+    }
+    entity
+}
+
+/// Take a client out of their room. A room is closed once no human is left in it.
+fn leave_room(
+    commands: &mut Commands,
+    outbox: &mut ServerOutbox,
+    lobby: &mut ServerLobby,
+    room_entities: &Query<(Entity, &RoomId)>,
+    bots: &Query<(), With<Bot>>,
+    client_id: ClientId,
+) {
+    let Some(room_id) = lobby.clients.remove(&client_id) else {
+        return;
+    };
+    let Some(room) = lobby.rooms.get_mut(&room_id) else {
+        return;
+    };
+    if let Some(player_entity) = room.remove_player(client_id) {
+        commands.entity(player_entity).despawn();
+    }
+    room.send(
+        outbox,
+        ServerChannel::ServerMessages,
+        &ServerMessages::PlayerRemove { id: client_id },
+    );
+
+    // Hand the room over to the longest-connected remaining human.
+    if room.host == Some(client_id) {
+        room.host = room
+            .players
+            .iter()
+            .filter(|(_, entity)| !bots.contains(**entity))
+            .map(|(id, _)| *id)
+            .min();
+        room.send_settings(outbox);
+    }
+
+    if room.host.is_none() {
+        println!("Closing room {}.", room.code);
+        for (entity, entity_room) in room_entities.iter() {
+            if *entity_room == room_id {
+                commands.entity(entity).despawn();
+            }
+        }
+        lobby.rooms.remove(&room_id);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn server_network_sync(
+    mut outbox: ResMut<ServerOutbox>,
+    lobby: Res<ServerLobby>,
+    query: Query<
+        (
+            Entity,
+            &RoomId,
+            &Transform,
+            Option<&FacingDirection>,
+            Option<&Player>,
+            Option<&Weapon>,
+            Has<Stunned>,
+            Has<Invulnerable>,
+        ),
+        Or<(With<Player>, With<Projectile>)>,
+    >,
+) {
+    // Every room only gets to see its own entities.
+    let mut snapshots: HashMap<RoomId, NetworkedEntities> = HashMap::new();
+    for (
+        entity,
+        room_id,
+        transform,
+        maybe_direction,
+        maybe_player,
+        maybe_weapon,
+        stunned,
+        invulnerable,
+    ) in query.iter()
+    {
+        let networked_entities = snapshots.entry(*room_id).or_default();
+        networked_entities.entities.push(entity);
+        networked_entities
+            .translations
+            .push(transform.translation.into());
+
+        networked_entities
+            .score
+            .push(maybe_player.map(|player| player.score));
+        networked_entities
+            .banked
+            .push(maybe_player.map(|player| player.banked));
+
+        networked_entities.weapons.push(
+            maybe_weapon
+                .zip(lobby.rooms.get(room_id))
+                .map(|(weapon, room)| weapon.state(&room.settings)),
+        );
+
+        networked_entities
+            .hit_states
+            .push(maybe_player.map(|_| HitState {
+                stunned,
+                invulnerable,
+            }));
+
+        networked_entities.facing_directions.push(
+            maybe_direction
+                .map(|direction| Some([direction.0.x, direction.0.y]))
+                .unwrap_or(None),
+        );
+    }
+
+    for (room_id, mut networked_entities) in snapshots {
+        networked_entities.match_secs_left = lobby
+            .rooms
+            .get(&room_id)
+            .and_then(|room| room.clock.as_ref())
+            .map(|clock| clock.0.remaining_secs());
+        outbox.send(
+            Recipient::Room(room_id),
+            ServerChannel::NetworkedEntities,
+            &networked_entities,
+        );
+    }
+}
+
+fn move_players_system(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut MovementController, &PlayerInput, Has<Stunned>)>,
+) {
+    for (e, mut controller, input, stunned) in query.iter_mut() {
+        let direction = input.direction();
+        // velocity.0.x = direction.x * PLAYER_MOVE_SPEED;
+        // velocity.0.z = direction.y * PLAYER_MOVE_SPEED;
+        // Stunned ducks go where the hit sends them.
+        controller.intent = if stunned { Vec2::ZERO } else { direction };
+        commands.entity(e).insert(FacingDirection(direction));
+    }
+}
+
+/// Spawn a projectile in front of its owner and tell the owner's room about it.
+fn spawn_projectile(
+    commands: &mut Commands,
+    outbox: &mut ServerOutbox,
+    room: &Room,
+    owner: Entity,
+    origin: Vec2,
+    direction: Vec2,
+    offset_distance: f32,
+) -> Entity {
+    let angle = direction.y.atan2(direction.x) - PI / 2.0;
+    let translation = (origin + direction * offset_distance).extend(10.);
+
+    let projectile_entity = commands
+        .spawn((
+            Transform::from_translation(translation).with_rotation(Quat::from_rotation_z(angle)),
+            room.id,
+        ))
+        .insert(projectile_collider())
+        .insert(FacingDirection(direction))
+        .insert(Projectile {
+            speed: room.settings.projectile_speed,
+            direction,
+            owner,
+            range_left: room.settings.projectile_range,
+            lifetime_left: PROJECTILE_LIFETIME_SECS,
+            arming_left: PROJECTILE_ARMING_DISTANCE,
+        })
+        .id();
+    room.send(
+        outbox,
+        ServerChannel::ServerMessages,
+        &ServerMessages::SpawnProjectile {
+            entity: projectile_entity,
+            translation: translation.into(),
+            angle,
+        },
+    );
+
+    projectile_entity
+}
+
+fn move_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    lobby: Res<ServerLobby>,
+    spatial_hash: Res<SpatialHash>,
+    mut query: Query<(Entity, &mut Projectile, &mut Transform, &Collider, &RoomId)>,
+    colliders: Query<
+        (Entity, &Transform, &Collider, &RoomId, Has<Invulnerable>),
+        Without<Projectile>,
+    >,
+    mut contacts: ResMut<Contacts>,
+) {
+    for (e, mut projectile, mut proj_transform, proj_collider, room_id) in &mut query {
+        let Some(room) = lobby.rooms.get(room_id) else {
+            continue;
+        };
+        let movement_this_frame =
+            projectile.direction.extend(0.0) * projectile.speed * time.delta_secs();
+
+        // Sweep along the whole step, so fast projectiles can't skip over anything,
+        // and hit whatever is in the way first.
+        let step = movement_this_frame.xy();
+        let nearby = spatial_hash.query(
+            Some(*room_id),
+            proj_transform.translation.xy() + step / 2.0,
+            proj_collider.size() + step.abs() + BROADPHASE_SLACK,
+        );
+        let first_hit = colliders
+            .iter_many(&nearby)
+            // Ducks that were just hit can't be hit again for a moment.
+            .filter(|(.., invulnerable)| !invulnerable)
+            .filter(|(collider_entity, _, collider, collider_room, _)| {
+                *collider_room == room_id
+                    && proj_collider.interacts_with(collider)
+                    && (projectile.owner != *collider_entity
+                        || (room.settings.self_hits && projectile.arming_left <= 0.0))
+            })
+            .filter_map(|hit| {
+                let (_, collider_transform, collider, _, _) = hit;
+                sweep_collision(
+                    proj_transform.translation.xy(),
+                    step,
+                    proj_collider,
+                    collider_transform.translation.xy(),
+                    collider,
+                )
+                .map(|time_of_impact| (time_of_impact, hit))
+            })
+            .min_by(|(a, _), (b, _)| a.total_cmp(b));
+
+        if let Some((_, (collider_entity, _, collider, _, _))) = first_hit {
+            // What happens next is up to `projectile_hit`.
+            contacts.report(&mut commands, e, &[(collider_entity, collider.layers)]);
+            continue;
+        }
+        proj_transform.translation += movement_this_frame;
+        projectile.arming_left -= movement_this_frame.length();
+
+        // Missed shots don't fly forever. Despawning tells the clients through
+        // `despawn_on_removal`.
+        projectile.range_left -= movement_this_frame.length();
+        projectile.lifetime_left -= time.delta_secs();
+        let out_of_bounds = proj_transform
+            .translation
+            .xy()
+            .abs()
+            .cmpgt(ARENA_HALF_EXTENTS + ARENA_MARGIN)
+            .any();
+        if projectile.range_left <= 0.0 || projectile.lifetime_left <= 0.0 || out_of_bounds {
+            commands.entity(e).despawn();
+        }
+    }
+}
+
+/// Projectiles stop at the first thing they hit, and ducks they hit drop some of their coins
+/// and get knocked back.
+fn projectile_hit(
+    trigger: Trigger<CollisionStarted>,
+    mut commands: Commands,
+    lobby: Res<ServerLobby>,
+    projectiles: Query<(&RoomId, &Projectile)>,
+    players: Query<(&Transform, &Player), Without<Invulnerable>>,
+    mut score_event: EventWriter<ScoreEvent>,
+    mut outbox: ResMut<ServerOutbox>,
+) {
+    let hit = trigger.event();
+    let Ok((room_id, projectile)) = projectiles.get(hit.entity) else {
+        return;
+    };
+    commands.entity(hit.entity).despawn();
+    let (Some(room), Ok((transform, player))) = (lobby.rooms.get(room_id), players.get(hit.other))
+    else {
+        return;
+    };
+    react_to_hit(&mut commands, hit.other, projectile.direction);
+    let penalty = i64::min(5, player.score);
+    score_event.send(ScoreEvent {
+        player: hit.other,
+        delta: -penalty,
+    });
+    // Drop what was lost in as few coins as it takes.
+    for kind in CoinKind::change(penalty) {
+        let mut rng = rand::thread_rng();
+        let player_pos = transform.translation;
+        let x_offset = rng.gen_range(-200.0..200.0); // You can adjust the upper bound here
+        let y_offset = rng.gen_range(-200.0..200.0); // You can adjust the upper bound here
+        let pos = player_pos + Vec3::new(x_offset, y_offset, 3.);
+        spawn_coin(&mut commands, &mut outbox, room, pos, kind);
+    }
+}
+
+/// Push overlapping ducks apart, so big ducks shove small ones out of the way and ducks the
+/// same size block each other. Every push is worked out from where the ducks stood before
+/// any of them moved, so the order they're visited in makes no difference. Nobody gets
+/// pushed into a wall.
+fn push_ducks(
+    lobby: Res<ServerLobby>,
+    spatial_hash: Res<SpatialHash>,
+    mut colliders: Query<(Entity, &mut Transform, &Collider, &RoomId, Option<&Mass>)>,
+) {
+    let mut ducks: HashMap<RoomId, Vec<(Entity, Vec2, f32, f32)>> = HashMap::new();
+    for (entity, transform, collider, room_id, mass) in &colliders {
+        let Some(mass) = mass else {
+            continue;
+        };
+        if lobby
+            .rooms
+            .get(room_id)
+            .is_some_and(|room| room.settings.duck_collisions)
+        {
+            ducks.entry(*room_id).or_default().push((
+                entity,
+                transform.translation.xy(),
+                collider.size().x / 2.0,
+                mass.0,
+            ));
+        }
+    }
+
+    let mut pushes = vec![];
+    for (room_id, mut room_ducks) in ducks {
+        room_ducks.sort_by_key(|(entity, ..)| *entity);
+        let mut room_pushes = vec![Vec2::ZERO; room_ducks.len()];
+        for (i, &(_, a, a_radius, a_mass)) in room_ducks.iter().enumerate() {
+            for (j, &(_, b, b_radius, b_mass)) in room_ducks.iter().enumerate().skip(i + 1) {
+                if let Some((a_push, b_push)) =
+                    push_apart((a, a_radius, a_mass), (b, b_radius, b_mass))
+                {
+                    room_pushes[i] += a_push;
+                    room_pushes[j] += b_push;
+                }
+            }
+        }
+
+        for (&(entity, position, _, _), push) in room_ducks.iter().zip(room_pushes) {
+            if push == Vec2::ZERO {
+                continue;
+            }
+            let Ok((_, _, collider, _, _)) = colliders.get(entity) else {
+                continue;
+            };
+            // Walls are checked against the duck's feet, like in `apply_movement`.
+            let feet = position - Vec2::new(0., 10.);
+            let mut mask = Vec2::ONE;
+            let nearby =
+                spatial_hash.query(Some(room_id), feet, collider.size() + push.abs() * 2.0);
+            for other in nearby {
+                let Ok((_, other_transform, other_collider, _, other_mass)) = colliders.get(other)
+                else {
+                    continue;
+                };
+                if other == entity
+                    || other_mass.is_some()
+                    || !other_collider.layers.intersects(CollisionLayers::SOLID)
+                    || !collider.interacts_with(other_collider)
+                {
+                    continue;
+                }
+                for (axis, index) in [(Vec2::X, 0), (Vec2::Y, 1)] {
+                    if check_collision(
+                        &(feet + push * axis).extend(0.),
+                        collider,
+                        &other_transform.translation,
+                        other_collider,
+                    ) {
+                        mask[index] = 0.;
+                    }
+                }
+            }
+            pushes.push((entity, push * mask));
+        }
+    }
+
+    for (entity, push) in pushes {
+        if let Ok((_, mut transform, ..)) = colliders.get_mut(entity) {
+            transform.translation += push.extend(0.);
+        }
+    }
+}
+
+/// Hand out every coin claimed this tick, exactly once, and tell the room who got it.
+fn resolve_coin_claims(
+    mut commands: Commands,
+    lobby: Res<ServerLobby>,
+    coins: Query<(Entity, &Coin, &RoomId), Changed<Coin>>,
+    mut score_event: EventWriter<ScoreEvent>,
+    mut outbox: ResMut<ServerOutbox>,
+) {
+    for (entity, coin, room_id) in &coins {
+        let Some(by) = coin.claimed_by else {
+            continue;
+        };
+        score_event.send(ScoreEvent {
+            player: by,
+            delta: coin.kind.value(),
+        });
+        if let Some(room) = lobby.rooms.get(room_id) {
+            room.send(
+                &mut outbox,
+                ServerChannel::ServerMessages,
+                &ServerMessages::CoinCollected { coin: entity, by },
+            );
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Tell the clients in an entity's room when it loses a replicated component,
+/// which for our replicated entities means they were despawned.
+fn despawn_on_removal<T: Component>(
+    trigger: Trigger<OnRemove, T>,
+    rooms: Query<&RoomId>,
+    lobby: Res<ServerLobby>,
+    mut outbox: ResMut<ServerOutbox>,
+) {
+    let entity = trigger.entity();
+    // Rooms that were just closed have nobody left to tell.
+    let Some(room) = rooms
+        .get(entity)
+        .ok()
+        .and_then(|room_id| lobby.rooms.get(room_id))
+    else {
+        return;
+    };
+    room.send(
+        &mut outbox,
+        ServerChannel::ServerMessages,
+        &ServerMessages::DespawnEntity { entity },
+    );
+}
+
+fn handle_score_event(
+    mut events: EventReader<ScoreEvent>,
+    mut commands: Commands,
+    lobby: Res<ServerLobby>,
+    mut player_query: Query<(
+        Entity,
+        &mut Transform,
+        &mut Player,
+        &mut MovementController,
+        &RoomId,
+    )>,
+) {
+    for event in events.read() {
+        if let Ok((entity, mut transform, mut player, mut movement, room_id)) =
+            player_query.get_mut(event.player)
+        {
+            let Some(room) = lobby.rooms.get(room_id) else {
+                continue;
+            };
+            player.score += event.delta;
+            let growth = Growth::from_score(player.score, &room.settings);
+            transform.scale = Vec2::splat(growth.scale).extend(1.0);
+            movement.max_speed = growth.max_speed;
+            movement.acceleration = growth.acceleration;
+            movement.deceleration = growth.deceleration;
+            commands
+                .entity(entity)
+                .insert((player_collider(growth), Mass(growth.mass)));
+            println!("Player {:?} score: {:?}", entity, player.score);
+        }
+    }
+}
+
+pub(super) fn spawn_coin(
+    commands: &mut Commands,
+    outbox: &mut ServerOutbox,
+    room: &Room,
+    position: Vec3,
+    kind: CoinKind,
+) -> Entity {
+    let coin_entity = commands
+        .spawn((
+            Name::new("Coin"),
+            Coin {
+                claimed_by: None,
+                kind,
+            },
+            Transform::from_translation(position)
+                .with_scale(Vec2::splat(1.5 * kind.size()).extend(1.)),
+            coin_collider(kind),
+            room.id,
+        ))
+        .id();
+    room.send(
+        outbox,
+        ServerChannel::ServerMessages,
+        &ServerMessages::SpawnCoin {
+            entity: coin_entity,
+            translation: position.into(),
+            kind,
+        },
+    );
+
+    coin_entity
+}
+
+/// Add a bot to a room. Bots are always ready; see [`super::bot`] for how they play.
+pub fn spawn_bot(
+    commands: &mut Commands,
+    outbox: &mut ServerOutbox,
+    room: &mut Room,
+    bot_id: &mut BotId,
+    bot: Bot,
+) -> Entity {
+    let client_id: ClientId = bot_id.0;
+    bot_id.0 += 1;
+
+    spawn_duck(commands, outbox, room, client_id, Some(bot))
+}
+
+/// (Re)generate a room's map whenever the seed or surfaces in its [`MatchSettings`] change.
+pub(super) fn generate_world(
+    mut commands: Commands,
+    mut outbox: ResMut<ServerOutbox>,
+    mut lobby: ResMut<ServerLobby>,
+    game_objects: Query<(Entity, &RoomId), With<ServerGameObject>>,
+) {
+    for (room_id, room) in lobby.rooms.iter_mut() {
+        if room.map_seed == Some(room.settings.map_seed)
+            && room.surface_patches == room.settings.surface_patches
+        {
+            continue;
+        }
+        room.map_seed = Some(room.settings.map_seed);
+        room.surface_patches = room.settings.surface_patches;
+        room.nav = None;
+        debug!(
+            "Generating the map for room {} with seed {}",
+            room.code, room.settings.map_seed
+        );
+
+        for (entity, object_room) in game_objects.iter() {
+            if object_room == room_id {
+                commands.entity(entity).despawn();
+            }
+        }
+
+        let mut rng = StdRng::seed_from_u64(room.settings.map_seed);
+        let obj_collider_sizes = [Vec2::new(0., 0.), Vec2::new(110., 80.), Vec2::new(26., 30.)];
+        let dirt_patches = [
+            Vec3::new(-250., 0., 2.),
+            Vec3::new(250., 0., 2.),
+            Vec3::new(0., 250., 2.),
+            Vec3::new(0., -250., 2.),
+            Vec3::new(176., 176., 2.),
+            Vec3::new(-176., 176., 2.),
+            Vec3::new(-176., -176., 2.),
+            Vec3::new(176., -176., 2.),
+        ];
+        // Some of the patches are mud or ice, taking turns.
+        let mut surfaces = [None; 8];
+        let surface_patches = (room.settings.surface_patches as usize).min(dirt_patches.len());
+        for (i, patch) in sample(&mut rng, dirt_patches.len(), surface_patches)
+            .into_iter()
+            .enumerate()
+        {
+            surfaces[patch] = Some(Surface::ALL[i % Surface::ALL.len()]);
+        }
+        for i in 0..8 {
+            let object = spawn_game_object(
+                &mut commands,
+                &mut outbox,
+                room,
+                surfaces[i].map_or(0, Surface::game_object_id),
+                Transform::from_translation(dirt_patches[i]).with_scale(Vec3::new(1.5, 1.5, 1.)),
+                Name::new("Game Object"),
+            );
+            if let Some(surface) = surfaces[i] {
+                commands.entity(object).insert(surface);
+            }
+        }
+
+        spawn_game_object(
+            &mut commands,
+            &mut outbox,
+            room,
+            1,
+            Transform::from_translation(Vec2::ZERO.extend(2.)).with_scale(Vec3::new(1.5, 1.5, 1.)),
+            (
+                Name::new("Pond"),
+                game_object_collider(1, obj_collider_sizes[1]),
+            ),
+        );
+
+        let num_trees = rng.gen_range(12..=20);
+
+        for _ in 0..num_trees {
+            // Generate a random angle between 0 and 2*pi
+            let angle = rng.gen_range(0.0..std::f32::consts::PI * 2.0);
+
+            // Generate a random distance greater than the minimum radius (e.g., 250)
+            let distance = rng.gen_range(270.0..500.0); // You can adjust the upper bound here
+
+            // Convert polar coordinates to Cartesian coordinates (x, y)
+            let x = distance * angle.cos();
+            let y = distance * angle.sin();
+            spawn_game_object(
+                &mut commands,
+                &mut outbox,
+                room,
+                2,
+                Transform::from_translation(Vec3::new(x, y, 3.))
+                    .with_scale(Vec3::new(1.5, 1.5, 1.)),
+                (
+                    Name::new("Tree"),
+                    game_object_collider(2, obj_collider_sizes[2]),
+                ),
+            );
+        }
+        let num_walls = 8; //fastrand::usize(4..=6);
+
+        let wall_base_pos = [
+            Vec3::new(-300., 0., 3.),
+            Vec3::new(300., 0., 3.),
+            Vec3::new(0., 300., 3.),
+            Vec3::new(0., -300., 3.),
+            Vec3::new(212., 212., 3.),
+            Vec3::new(-212., 212., 3.),
+            Vec3::new(-212., -212., 3.),
+            Vec3::new(212., -212., 3.),
+        ];
+        println!("SPAWNING WALLS");
+        for i in 0..num_walls {
+            // Generate a random distance greater than the minimum radius (e.g., 250)
+            let x_offset = rng.gen_range(1.0..2.5); // You can adjust the upper bound here
+            let y_offset = rng.gen_range(1.0..1.4); // You can adjust the upper bound here
+            let pos = wall_base_pos[i] * Vec3::new(x_offset, y_offset, 1.);
+            let wall_type = rng.gen_range(0..=3);
+            let size = match wall_type {
+                0 => Vec2::new(64., 48.),
+                1 => Vec2::new(94., 48.),
+                2 => Vec2::new(32., 80.),
+                _ => Vec2::new(32., 114.),
+            };
+            spawn_game_object(
+                &mut commands,
+                &mut outbox,
+                room,
+                3 + wall_type,
+                Transform::from_translation(pos).with_scale(Vec3::new(1.5, 1.5, 1.)),
+                (
+                    Name::new("Wall"),
+                    game_object_collider(3 + wall_type, size * 1.5),
+                ),
+            );
+        }
+
+        // Coins like the dirt patches, some more than others.
+        room.coin_director.hotspots = dirt_patches
+            .iter()
+            .map(|patch| Hotspot {
+                center: patch.xy(),
+                radius: 80.,
+                weight: rng.gen_range(0.5..2.0),
+            })
+            .collect();
+    }
+}
+
+/// Spawn a static map object and tell the clients in its room about it.
+fn spawn_game_object(
+    commands: &mut Commands,
+    outbox: &mut ServerOutbox,
+    room: &Room,
+    id: u64,
+    transform: Transform,
+    bundle: impl Bundle,
+) -> Entity {
+    let entity = commands
+        .spawn((bundle, transform, ServerGameObject(id), room.id))
+        .id();
+    room.send(
+        outbox,
+        ServerChannel::ServerMessages,
+        &ServerMessages::SpawnGameObject {
+            entity,
+            id,
+            translation: transform.translation.into(),
+        },
+    );
+
+    entity
+}
+
+/// End a room's match when its clock runs out, tell everyone how they did, and set the room
+/// up for the next one.
+#[allow(clippy::type_complexity)]
+fn update_match_clock(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut lobby: ResMut<ServerLobby>,
+    mut outbox: ResMut<ServerOutbox>,
+    players: Query<(&Player, Option<&Bot>)>,
+    room_entities: Query<(Entity, &RoomId), (Without<ServerGameObject>, Without<Nest>)>,
+) {
+    for room in lobby.rooms.values_mut() {
+        let Some(clock) = room.clock.as_mut() else {
+            continue;
+        };
+        if !clock.0.tick(time.delta()).just_finished() {
+            continue;
+        }
+        room.clock = None;
+
+        let mut results: Vec<_> = room
+            .players
+            .values()
+            .filter_map(|entity| players.get(*entity).ok())
+            .map(|(player, _)| MatchResult {
+                id: player.id,
+                carried: player.score,
+                banked: player.banked,
+                total: player.total(),
+            })
+            .collect();
+        results.sort_by_key(|result| (Reverse(result.total), result.id));
+        println!("Match in room {} over! Results: {:?}", room.code, results);
+        room.send(
+            &mut outbox,
+            ServerChannel::ServerMessages,
+            &ServerMessages::EndGame { results },
+        );
+        reset_room(&mut commands, &mut outbox, room, &players, &room_entities);
+    }
+}
+
+/// Put a room back in its lobby after a match: everyone gets a fresh duck with nothing carried
+/// or banked, humans have to ready up again, and the map is sent again.
+#[allow(clippy::type_complexity)]
+fn reset_room(
+    commands: &mut Commands,
+    outbox: &mut ServerOutbox,
+    room: &mut Room,
+    players: &Query<(&Player, Option<&Bot>)>,
+    room_entities: &Query<(Entity, &RoomId), (Without<ServerGameObject>, Without<Nest>)>,
+) {
+    // Bots keep how they play, but nothing else.
+    let mut ducks: Vec<_> = room
+        .players
+        .iter()
+        .map(|(id, entity)| {
+            let bot = players
+                .get(*entity)
+                .ok()
+                .and_then(|(_, bot)| bot)
+                .map(|bot| Bot::new(bot.difficulty.clone(), bot.personality.clone()));
+            (*id, bot)
+        })
+        .collect();
+    ducks.sort_by_key(|(id, _)| *id);
+
+    // Nests go with their ducks, and `generate_world` replaces the map.
+    for (entity, entity_room) in room_entities.iter() {
+        if *entity_room == room.id {
+            commands.entity(entity).despawn();
+        }
+    }
+    room.players.clear();
+    room.spawn_slots.clear();
+    room.map_seed = None;
+
+    for (client_id, bot) in ducks {
+        spawn_duck(commands, outbox, room, client_id, bot);
+    }
+}
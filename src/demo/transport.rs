@@ -0,0 +1,97 @@
+//! The seam between the game and the network.
+//!
+//! The simulation in [`super::server`] only reads client events and writes to the
+//! [`ServerOutbox`], and the client only reads its [`ClientInbox`] and writes to its
+//! [`ClientOutbox`]. Whatever transport is running moves messages between them: renet for
+//! online play, or a direct hand-off in offline practice.
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_renet2::prelude::ClientId;
+use serde::Serialize;
+
+use super::lib::{PlayerCommand, PlayerInput, RoomId, ServerChannel};
+
+/// Who a message from the server is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recipient {
+    Client(ClientId),
+    /// Every client in the room. Bots are skipped, as nobody is listening for them.
+    Room(RoomId),
+}
+
+#[derive(Debug)]
+pub struct OutgoingMessage {
+    pub recipient: Recipient,
+    pub channel: ServerChannel,
+    pub payload: Vec<u8>,
+}
+
+/// Messages the simulation wants to send, drained every frame by the transport.
+#[derive(Debug, Default, Resource)]
+pub struct ServerOutbox {
+    messages: Vec<OutgoingMessage>,
+}
+
+impl ServerOutbox {
+    pub fn send(&mut self, recipient: Recipient, channel: ServerChannel, message: &impl Serialize) {
+        self.messages.push(OutgoingMessage {
+            recipient,
+            channel,
+            payload: bincode::serialize(message).unwrap(),
+        });
+    }
+
+    pub fn drain(&mut self) -> impl Iterator<Item = OutgoingMessage> + '_ {
+        self.messages.drain(..)
+    }
+}
+
+/// A client asked the server to do something.
+#[derive(Debug, Event)]
+pub struct ClientCommand {
+    pub client_id: ClientId,
+    pub command: PlayerCommand,
+}
+
+/// A client's latest input.
+#[derive(Debug, Event)]
+pub struct ClientInput {
+    pub client_id: ClientId,
+    pub input: PlayerInput,
+}
+
+/// A client went away.
+#[derive(Debug, Event)]
+pub struct ClientLeft {
+    pub client_id: ClientId,
+}
+
+/// Messages from the server waiting to be handled by the client.
+#[derive(Debug, Default, Resource)]
+pub struct ClientInbox {
+    messages: VecDeque<(ServerChannel, Vec<u8>)>,
+}
+
+impl ClientInbox {
+    pub fn push(&mut self, channel: ServerChannel, payload: Vec<u8>) {
+        self.messages.push_back((channel, payload));
+    }
+
+    /// Take the oldest message on a channel.
+    pub fn receive(&mut self, channel: ServerChannel) -> Option<Vec<u8>> {
+        let index = self
+            .messages
+            .iter()
+            .position(|(message_channel, _)| *message_channel == channel)?;
+        self.messages.remove(index).map(|(_, payload)| payload)
+    }
+}
+
+/// What the client wants to tell the server this frame.
+#[derive(Debug, Default, Resource)]
+pub struct ClientOutbox {
+    pub input: Option<PlayerInput>,
+    pub commands: Vec<PlayerCommand>,
+}
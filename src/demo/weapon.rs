@@ -0,0 +1,119 @@
+//! Fire rate and ammo. The server only lets a duck shoot when its [`Weapon`] is ready, no
+//! matter how often the client asks, and replicates a [`WeaponState`] for the HUD.
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::screens::Screen;
+
+use super::{
+    client::ControlledPlayer,
+    lib::{MatchSettings, WeaponState},
+};
+
+const RING_RADIUS: f32 = 22.0;
+const RING_COLOR: Color = Color::srgb(1.0, 1.0, 1.0);
+const RELOAD_COLOR: Color = Color::srgb(1.0, 0.6, 0.1);
+
+/// Client side: a cooldown ring and the rounds left around the local duck.
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Update, draw_weapon_hud.run_if(in_state(Screen::Gameplay)));
+}
+
+/// A duck's weapon on the server. How fast it shoots comes from the room's [`MatchSettings`].
+#[derive(Debug, Component)]
+pub struct Weapon {
+    cooldown: Timer,
+    /// Shots fired since the last reload.
+    fired: u32,
+    reloading: bool,
+}
+
+impl Default for Weapon {
+    fn default() -> Self {
+        Self {
+            // Finishes on the first tick, so ducks can shoot straight away.
+            cooldown: Timer::from_seconds(0.0, TimerMode::Once),
+            fired: 0,
+            reloading: false,
+        }
+    }
+}
+
+impl Weapon {
+    pub fn ready(&self) -> bool {
+        !self.reloading && self.cooldown.finished()
+    }
+
+    /// Use up a shot. Callers should check [`Weapon::ready`] first.
+    pub fn fire(&mut self, settings: &MatchSettings) {
+        self.fired += 1;
+        if settings.magazine_size > 0 && self.fired >= settings.magazine_size {
+            self.reloading = true;
+            self.cooldown = Timer::from_seconds(settings.reload_secs, TimerMode::Once);
+        } else {
+            self.cooldown = Timer::from_seconds(settings.fire_cooldown_secs, TimerMode::Once);
+        }
+    }
+
+    pub fn state(&self, settings: &MatchSettings) -> WeaponState {
+        WeaponState {
+            cooldown: self.cooldown.fraction_remaining(),
+            rounds: (settings.magazine_size > 0)
+                .then(|| settings.magazine_size.saturating_sub(self.fired)),
+            reloading: self.reloading,
+        }
+    }
+}
+
+/// Count down cooldowns, and refill magazines once a reload is done.
+pub(super) fn tick_weapons(time: Res<Time>, mut weapons: Query<&mut Weapon>) {
+    for mut weapon in &mut weapons {
+        weapon.cooldown.tick(time.delta());
+        if weapon.reloading && weapon.cooldown.finished() {
+            weapon.reloading = false;
+            weapon.fired = 0;
+        }
+    }
+}
+
+fn draw_weapon_hud(
+    mut gizmos: Gizmos,
+    player: Query<(&Transform, &WeaponState), With<ControlledPlayer>>,
+) {
+    let Ok((transform, weapon)) = player.get_single() else {
+        return;
+    };
+    let position = transform.translation.xy();
+    let radius = RING_RADIUS * transform.scale.x;
+    let color = if weapon.reloading {
+        RELOAD_COLOR
+    } else {
+        RING_COLOR
+    };
+    if weapon.cooldown > 0.0 {
+        gizmos.arc_2d(
+            Isometry2d::from_translation(position),
+            weapon.cooldown * TAU,
+            radius,
+            color,
+        );
+    }
+    // One pip per round left, in a row under the duck.
+    if let Some(rounds) = weapon.rounds {
+        let spacing = 6.0;
+        let start = position
+            + Vec2::new(
+                -spacing * (rounds.saturating_sub(1)) as f32 / 2.0,
+                -radius - 6.0,
+            );
+        for round in 0..rounds {
+            gizmos.circle_2d(
+                Isometry2d::from_translation(start + Vec2::X * spacing * round as f32),
+                2.0,
+                RING_COLOR,
+            );
+        }
+    }
+}
@@ -0,0 +1,262 @@
+//! A screen listing the servers known to the registry.
+
+#[cfg(not(target_family = "wasm"))]
+use std::net::{Ipv4Addr, UdpSocket};
+use std::time::Duration;
+
+use bevy::{hierarchy::ChildBuild, prelude::*, ui::Val::*, utils::Instant};
+use bevy_mod_reqwest::{BevyReqwest, JsonResponse, ReqwestErrorEvent, ReqwestResponseEvent};
+
+use crate::{
+    demo::{
+        client::ServerAddress,
+        registry::{ServerInfo, DEFAULT_REGISTRY_URL, DISCOVERY_PORT, DISCOVERY_REQUEST},
+    },
+    screens::Screen,
+    theme::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ServerListings>();
+    app.add_systems(
+        OnEnter(Screen::Browse),
+        (spawn_browse_screen, fetch_server_list),
+    );
+    app.add_systems(
+        Update,
+        update_server_list
+            .run_if(in_state(Screen::Browse).and(resource_changed::<ServerListings>)),
+    );
+
+    #[cfg(not(target_family = "wasm"))]
+    {
+        app.add_systems(OnEnter(Screen::Browse), discover_lan_servers);
+        app.add_systems(
+            Update,
+            receive_lan_servers.run_if(resource_exists::<LanDiscovery>),
+        );
+        app.add_systems(OnExit(Screen::Browse), |mut commands: Commands| {
+            commands.remove_resource::<LanDiscovery>();
+        });
+    }
+}
+
+/// The servers the registry told us about, along with the ping we measured to each.
+#[derive(Resource, Debug, Default)]
+pub struct ServerListings {
+    pub servers: Vec<ServerListing>,
+    pub status: String,
+}
+
+impl ServerListings {
+    /// Add a server, or update it if we already heard about it from elsewhere.
+    fn add(&mut self, info: ServerInfo, ping: Option<Duration>, lan: bool) {
+        match self
+            .servers
+            .iter_mut()
+            .find(|listing| listing.info.native_addr == info.native_addr)
+        {
+            Some(listing) => {
+                listing.info = info;
+                listing.ping = ping.or(listing.ping);
+                listing.lan |= lan;
+            }
+            None => self.servers.push(ServerListing { info, ping, lan }),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ServerListing {
+    pub info: ServerInfo,
+    pub ping: Option<Duration>,
+    /// Whether the server answered our LAN discovery broadcast.
+    pub lan: bool,
+}
+
+#[derive(Component)]
+struct ServerList;
+
+fn spawn_browse_screen(mut commands: Commands) {
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::Browse))
+        .with_children(|children| {
+            children.header("Servers");
+            children.spawn((
+                Name::new("Server List"),
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Px(10.0),
+                    ..default()
+                },
+                ServerList,
+            ));
+            children.button("Refresh").observe(refresh_server_list);
+            children.button("Back").observe(enter_title_screen);
+        });
+}
+
+fn fetch_server_list(mut client: BevyReqwest, mut listings: ResMut<ServerListings>) {
+    listings.servers.clear();
+    listings.status = "Looking for servers...".to_string();
+
+    let request = client
+        .get(format!("{}/servers", DEFAULT_REGISTRY_URL))
+        .build()
+        .unwrap();
+    client
+        .send(request)
+        .on_json_response(
+            |trigger: Trigger<JsonResponse<Vec<ServerInfo>>>,
+             mut client: BevyReqwest,
+             mut listings: ResMut<ServerListings>| {
+                let servers = &trigger.event().0;
+                listings.status = if servers.is_empty() {
+                    "No servers in the registry.".to_string()
+                } else {
+                    String::new()
+                };
+                for info in servers {
+                    ping_server(&mut client, info);
+                    listings.add(info.clone(), None, false);
+                }
+            },
+        )
+        .on_error(
+            |trigger: Trigger<ReqwestErrorEvent>, mut listings: ResMut<ServerListings>| {
+                warn!("Could not reach the server registry: {:?}", trigger.event().0);
+                listings.status = "Could not reach the server registry.".to_string();
+            },
+        );
+}
+
+/// Time a request to the server's info endpoint.
+fn ping_server(client: &mut BevyReqwest, info: &ServerInfo) {
+    let native_addr = info.native_addr;
+    let started = Instant::now();
+    let request = client
+        .get(format!("http://{}/info", info.http_addr))
+        .build()
+        .unwrap();
+    client.send(request).on_response(
+        move |_trigger: Trigger<ReqwestResponseEvent>, mut listings: ResMut<ServerListings>| {
+            if let Some(listing) = listings
+                .servers
+                .iter_mut()
+                .find(|listing| listing.info.native_addr == native_addr)
+            {
+                listing.ping = Some(started.elapsed());
+            }
+        },
+    );
+}
+
+fn update_server_list(
+    mut commands: Commands,
+    listings: Res<ServerListings>,
+    list: Query<Entity, With<ServerList>>,
+) {
+    let Ok(list) = list.get_single() else {
+        return;
+    };
+    commands
+        .entity(list)
+        .despawn_descendants()
+        .with_children(|children| {
+            if !listings.status.is_empty() {
+                children.label(listings.status.clone());
+            }
+            for listing in &listings.servers {
+                let info = &listing.info;
+                let ping = listing
+                    .ping
+                    .map_or("?".to_string(), |ping| format!("{} ms", ping.as_millis()));
+                children
+                    .spawn((
+                        Name::new("Server Entry"),
+                        Node {
+                            align_items: AlignItems::Center,
+                            column_gap: Px(10.0),
+                            ..default()
+                        },
+                    ))
+                    .with_children(|row| {
+                        row.label(format!(
+                            "{}{} - {}/{} - {} - {} - {}",
+                            info.name,
+                            if listing.lan { " (LAN)" } else { "" },
+                            info.players,
+                            info.max_players,
+                            info.map,
+                            info.state,
+                            ping
+                        ));
+                        let native_addr = info.native_addr;
+                        row.button("Join").observe(
+                            move |_trigger: Trigger<OnPress>,
+                                  mut server_address: ResMut<ServerAddress>,
+                                  mut next_screen: ResMut<NextState<Screen>>| {
+                                server_address.0 = native_addr;
+                                next_screen.set(Screen::Lobby);
+                            },
+                        );
+                    });
+            }
+        });
+}
+
+/// Socket we broadcast LAN discovery requests on, and when we sent the last one.
+#[cfg(not(target_family = "wasm"))]
+#[derive(Resource)]
+struct LanDiscovery {
+    socket: UdpSocket,
+    sent_at: Instant,
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn discover_lan_servers(mut commands: Commands) {
+    let socket = match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)) {
+        Ok(socket) => socket,
+        Err(error) => {
+            warn!("Could not open a socket for LAN discovery: {}", error);
+            return;
+        }
+    };
+    socket.set_broadcast(true).unwrap();
+    socket.set_nonblocking(true).unwrap();
+    if let Err(error) = socket.send_to(DISCOVERY_REQUEST, (Ipv4Addr::BROADCAST, DISCOVERY_PORT)) {
+        warn!("Could not broadcast LAN discovery: {}", error);
+    }
+    commands.insert_resource(LanDiscovery {
+        socket,
+        sent_at: Instant::now(),
+    });
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn receive_lan_servers(discovery: Res<LanDiscovery>, mut listings: ResMut<ServerListings>) {
+    let mut buffer = [0; 1024];
+    while let Ok((len, from)) = discovery.socket.recv_from(&mut buffer) {
+        match bincode::deserialize::<ServerInfo>(&buffer[..len]) {
+            Ok(info) => listings.add(info, Some(discovery.sent_at.elapsed()), true),
+            Err(error) => warn!("Bad discovery reply from {}: {}", from, error),
+        }
+    }
+}
+
+fn refresh_server_list(
+    _trigger: Trigger<OnPress>,
+    #[cfg(not(target_family = "wasm"))] commands: Commands,
+    client: BevyReqwest,
+    listings: ResMut<ServerListings>,
+) {
+    fetch_server_list(client, listings);
+    #[cfg(not(target_family = "wasm"))]
+    discover_lan_servers(commands);
+}
+
+fn enter_title_screen(_trigger: Trigger<OnPress>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
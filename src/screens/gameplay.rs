@@ -3,15 +3,9 @@
 use std::process::CommandArgs;
 
 use bevy::{input::common_conditions::input_just_pressed, prelude::*};
-use bevy_renet2::prelude::RenetServer;
 
-use crate::demo::client::PLAYER_BASE_COLLIDER_SIZE;
-use crate::demo::lib::Player;
-use crate::demo::lib::ServerChannel;
-use crate::demo::lib::ServerMessages;
-use crate::demo::physics::Collider;
+use crate::demo::lib::MatchSettings;
 use crate::demo::player;
-use crate::demo::player::Coin;
 use crate::theme::widgets::Containers;
 use crate::theme::widgets::Widgets;
 use crate::{
@@ -27,8 +21,19 @@ pub(super) fn plugin(app: &mut App) {
         OnEnter(Screen::Lobby),
         (play_gameplay_music, spawn_score_text),
     );
-    app.add_systems(OnExit(Screen::Gameplay), stop_music);
+    app.add_systems(OnExit(Screen::Gameplay), (stop_music, clear_banners));
     app.add_event::<ScoreEvent>();
+    app.add_observer(announce_coin_rain);
+    app.add_observer(announce);
+    app.add_systems(
+        Update,
+        (update_coin_rain_text, update_announcement_text).run_if(in_state(Screen::Gameplay)),
+    );
+    app.add_systems(
+        Update,
+        update_match_clock_text
+            .run_if(in_state(Screen::Gameplay).and(resource_exists::<MatchClock>)),
+    );
     app.add_systems(
         Update,
         return_to_title_screen
@@ -87,6 +92,54 @@ fn return_to_title_screen(mut next_screen: ResMut<NextState<Screen>>) {
 #[derive(Component)]
 pub struct ScoreText;
 
+#[derive(Component)]
+pub struct MatchClockText;
+
+#[derive(Component)]
+pub struct CoinRainText;
+
+/// How long "Coin rain!" stays up once it starts.
+const COIN_RAIN_BANNER_SECS: f32 = 2.0;
+
+/// The server warned that a coin rain starts in `secs` seconds.
+#[derive(Event, Debug)]
+pub struct CoinRainWarning {
+    pub secs: f32,
+}
+
+/// Counts down to the coin rain, then keeps the banner up a little longer.
+#[derive(Resource, Debug)]
+struct CoinRainBanner(Timer);
+
+#[derive(Component)]
+pub struct AnnouncementText;
+
+/// How long an [`Announce`] message stays up.
+const ANNOUNCEMENT_SECS: f32 = 3.0;
+
+/// Show a message to the player for a few seconds, under the coin rain banner.
+#[derive(Event, Debug)]
+pub struct Announce {
+    pub message: String,
+}
+
+#[derive(Resource, Debug)]
+struct AnnouncementBanner(Timer);
+
+/// Counts down the remaining time of the current match. The server runs the real one, and
+/// keeps the clients' copies in step.
+#[derive(Resource, Debug)]
+pub struct MatchClock(pub Timer);
+
+impl MatchClock {
+    pub fn new(settings: &MatchSettings) -> Self {
+        Self(Timer::from_seconds(
+            settings.match_length_secs,
+            TimerMode::Once,
+        ))
+    }
+}
+
 fn spawn_score_text(mut commands: Commands) {
     commands
         .ui_root()
@@ -99,57 +152,100 @@ fn spawn_score_text(mut commands: Commands) {
 
                 ..default()
             });
+            children.label("").insert(MatchClockText).insert(Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.0),
+                top: Val::Px(10.0),
+                ..default()
+            });
+            children.label("").insert(CoinRainText).insert(Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                ..default()
+            });
+            children.label("").insert(AnnouncementText).insert(Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(50.0),
+                ..default()
+            });
         });
 }
 
-pub fn calculate_score_growth(score: i64) -> f32 {
-    score as f32 * 0.1
+fn update_match_clock_text(
+    time: Res<Time>,
+    mut clock: ResMut<MatchClock>,
+    mut text_query: Query<&mut Text, With<MatchClockText>>,
+) {
+    clock.0.tick(time.delta());
+    let remaining = clock.0.remaining_secs().ceil() as u32;
+    for mut text in &mut text_query {
+        text.0 = format!("{}:{:02}", remaining / 60, remaining % 60);
+    }
+}
+
+fn announce_coin_rain(trigger: Trigger<CoinRainWarning>, mut commands: Commands) {
+    commands.insert_resource(CoinRainBanner(Timer::from_seconds(
+        trigger.event().secs + COIN_RAIN_BANNER_SECS,
+        TimerMode::Once,
+    )));
+}
+
+fn clear_banners(mut commands: Commands) {
+    commands.remove_resource::<CoinRainBanner>();
+    commands.remove_resource::<AnnouncementBanner>();
 }
 
-pub fn handle_score_event(
-    mut events: EventReader<ScoreEvent>,
+fn announce(
+    trigger: Trigger<Announce>,
     mut commands: Commands,
-    mut player_query: Query<(Entity, &mut Transform, &mut Player)>,
+    mut text_query: Query<&mut Text, With<AnnouncementText>>,
 ) {
-    for event in events.read() {
-        if let Ok((entity, mut transform, mut player)) = player_query.get_mut(event.player) {
-            player.score += event.delta;
-            let score_growth = calculate_score_growth(player.score);
-            transform.scale = Vec3::splat(score_growth);
-            commands.entity(entity).insert(Collider {
-                size: PLAYER_BASE_COLLIDER_SIZE * score_growth,
-                collides_with_player: true,
-                collides_with_projectile: true,
-            });
-            println!("Player {:?} score: {:?}", entity, player.score);
-        }
+    commands.insert_resource(AnnouncementBanner(Timer::from_seconds(
+        ANNOUNCEMENT_SECS,
+        TimerMode::Once,
+    )));
+    for mut text in &mut text_query {
+        text.0 = trigger.event().message.clone();
     }
 }
 
-pub fn spawn_coin(
-    commands: &mut Commands,
-    server: &mut ResMut<RenetServer>,
-    position: Vec3,
-) -> Entity {
-    let coin_entity = commands
-        .spawn((
-            Name::new("Coin"),
-            Coin { claimed_by: None },
-            Transform::from_translation(position).with_scale(Vec3::new(1.5, 1.5, 1.)),
-            StateScoped(Screen::Gameplay),
-            Collider {
-                size: Vec2::new(20., 24.),
-                collides_with_player: true,
-                collides_with_projectile: false,
-            },
-        ))
-        .id();
-    let message = ServerMessages::SpawnCoin {
-        entity: coin_entity,
-        translation: position.into(),
+fn update_announcement_text(
+    mut commands: Commands,
+    time: Res<Time>,
+    banner: Option<ResMut<AnnouncementBanner>>,
+    mut text_query: Query<&mut Text, With<AnnouncementText>>,
+) {
+    let Some(mut banner) = banner else {
+        return;
     };
-    let message = bincode::serialize(&message).unwrap();
-    server.broadcast_message(ServerChannel::ServerMessages, message);
+    if banner.0.tick(time.delta()).finished() {
+        commands.remove_resource::<AnnouncementBanner>();
+        for mut text in &mut text_query {
+            text.0.clear();
+        }
+    }
+}
 
-    coin_entity
+fn update_coin_rain_text(
+    mut commands: Commands,
+    time: Res<Time>,
+    banner: Option<ResMut<CoinRainBanner>>,
+    mut text_query: Query<&mut Text, With<CoinRainText>>,
+) {
+    let Some(mut banner) = banner else {
+        return;
+    };
+    banner.0.tick(time.delta());
+    let until_rain = banner.0.remaining_secs() - COIN_RAIN_BANNER_SECS;
+    let message = if banner.0.finished() {
+        commands.remove_resource::<CoinRainBanner>();
+        String::new()
+    } else if until_rain > 0.0 {
+        format!("Coin rain in {}...", until_rain.ceil() as u32)
+    } else {
+        "Coin rain!".to_string()
+    };
+    for mut text in &mut text_query {
+        text.0 = message.clone();
+    }
 }
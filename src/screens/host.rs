@@ -0,0 +1,176 @@
+//! Host a game from the client, by running the `server` binary as a managed child process
+//! and connecting to it. Other players can then find it like any other server, in the
+//! registry unless the host opts out, and on the LAN either way.
+
+use std::{
+    io,
+    net::SocketAddr,
+    process::{Child, Command},
+};
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use bevy_renet2::prelude::RenetClient;
+
+use crate::{
+    demo::{
+        client::{ClientRoom, ServerAddress},
+        lib::{PlayerCommand, DEFAULT_PORT},
+        registry::DEFAULT_REGISTRY_URL,
+    },
+    screens::Screen,
+    theme::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<HostSettings>();
+    app.add_systems(OnEnter(Screen::Host), spawn_host_screen);
+    app.add_systems(Update, host_window.run_if(in_state(Screen::Host)));
+    app.add_systems(OnEnter(Screen::Title), stop_hosting);
+    app.add_systems(
+        Update,
+        create_hosted_room.run_if(
+            in_state(Screen::Lobby)
+                .and(resource_exists::<HostedServer>)
+                .and(resource_exists::<RenetClient>),
+        ),
+    );
+}
+
+#[derive(Resource, Debug)]
+struct HostSettings {
+    name: String,
+    port: u16,
+    /// Whether to register with the server registry, or only answer LAN discovery.
+    listed: bool,
+}
+
+impl Default for HostSettings {
+    fn default() -> Self {
+        Self {
+            name: "Duck Battles".to_string(),
+            port: DEFAULT_PORT,
+            listed: true,
+        }
+    }
+}
+
+/// The server we're hosting. It is stopped when this resource is dropped, i.e. when the host
+/// goes back to the title screen or the game exits.
+#[derive(Resource, Debug)]
+pub struct HostedServer {
+    process: Child,
+    room_requested: bool,
+}
+
+impl HostedServer {
+    fn start(settings: &HostSettings) -> io::Result<Self> {
+        // The server binary is built next to the game's.
+        let exe = std::env::current_exe()?
+            .with_file_name(format!("server{}", std::env::consts::EXE_SUFFIX));
+        let mut command = Command::new(exe);
+        command
+            .arg("--headless")
+            .args(["--port", &settings.port.to_string()])
+            .args(["--name", &settings.name]);
+        if settings.listed {
+            command.args(["--registry", DEFAULT_REGISTRY_URL]);
+        }
+        let process = command.spawn()?;
+        Ok(Self {
+            process,
+            room_requested: false,
+        })
+    }
+
+    fn stop(&mut self) {
+        // The process may already have exited, in which case there's nothing to do.
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+impl Drop for HostedServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn spawn_host_screen(mut commands: Commands) {
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::Host))
+        .with_children(|children| {
+            children.header("Host Game");
+            children.button("Back").observe(enter_title_screen);
+        });
+}
+
+fn host_window(
+    mut commands: Commands,
+    mut egui_contexts: EguiContexts,
+    mut settings: ResMut<HostSettings>,
+    mut hosted: Option<ResMut<HostedServer>>,
+    mut server_address: ResMut<ServerAddress>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut error: Local<Option<String>>,
+) {
+    egui::Window::new("Host Game")
+        .resizable(false)
+        .show(egui_contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Server name");
+                ui.text_edit_singleline(&mut settings.name);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Port");
+                ui.add(egui::DragValue::new(&mut settings.port).range(1024..=65535));
+            });
+            ui.checkbox(&mut settings.listed, "List in the server browser");
+            if !settings.listed {
+                ui.label("Only players on your local network will see this game.");
+            }
+            if ui.button("Host").clicked() {
+                // Free the port before starting a new server.
+                if let Some(hosted) = hosted.as_mut() {
+                    hosted.stop();
+                }
+                match HostedServer::start(&settings) {
+                    Ok(hosted) => {
+                        commands.insert_resource(hosted);
+                        server_address.0 = SocketAddr::from(([127, 0, 0, 1], settings.port));
+                        *error = None;
+                        next_screen.set(Screen::Lobby);
+                    }
+                    Err(err) => *error = Some(format!("Could not start the server: {}", err)),
+                }
+            }
+            if let Some(error) = &*error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+        });
+}
+
+/// Open a room as soon as we're connected to our own server, so friends only need the code.
+fn create_hosted_room(
+    client: Res<RenetClient>,
+    room: Res<ClientRoom>,
+    mut hosted: ResMut<HostedServer>,
+    mut player_commands: EventWriter<PlayerCommand>,
+) {
+    if hosted.room_requested || !client.is_connected() || room.code.is_some() {
+        return;
+    }
+    hosted.room_requested = true;
+    player_commands.send(PlayerCommand::CreateRoom);
+}
+
+/// Back on the title screen, the hosted room is gone with us, so stop the server rather than
+/// keep it running, and listed, with nobody in it.
+fn stop_hosting(mut commands: Commands) {
+    commands.remove_resource::<HostedServer>();
+}
+
+fn enter_title_screen(_trigger: Trigger<OnPress>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
@@ -2,14 +2,32 @@
 //! This reduces stuttering, especially for audio on WASM.
 
 use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use bevy_renet2::prelude::{ClientId, RenetClient};
 
-use crate::{demo::lib::Player, screens::Screen};
+use crate::{
+    demo::{
+        bot::BotProfiles,
+        client::{ClientLobby, ClientRoom, CurrentClientId, LobbyHost, MatchResults},
+        lib::{GrowthCurve, MatchSettings, Player, PlayerCommand},
+    },
+    screens::Screen,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_event::<ToggleReadyEvent>();
+    app.init_resource::<BotProfiles>();
     app.add_systems(
         Update,
-        (add_ready_checker, update_ready_checker).run_if(in_state(Screen::Lobby)),
+        (
+            add_ready_checker,
+            update_ready_checker,
+            room_window,
+            match_settings_window.run_if(in_room),
+            bots_window.run_if(in_room),
+            results_window.run_if(resource_exists::<MatchResults>),
+        )
+            .run_if(in_state(Screen::Lobby)),
     );
     app.add_systems(OnExit(Screen::Lobby), despawn_ready_checker);
 }
@@ -75,3 +93,305 @@ fn despawn_ready_checker(mut commands: Commands, ready_query: Query<Entity, With
         commands.entity(ready_entity).despawn_recursive();
     }
 }
+
+fn in_room(room: Res<ClientRoom>) -> bool {
+    room.code.is_some()
+}
+
+/// Create a new room, or join a friend's room by entering its code.
+fn room_window(
+    mut egui_contexts: EguiContexts,
+    room: Res<ClientRoom>,
+    client: Option<Res<RenetClient>>,
+    mut code_input: Local<String>,
+    mut player_commands: EventWriter<PlayerCommand>,
+) {
+    egui::Window::new("Room")
+        .resizable(false)
+        .show(egui_contexts.ctx_mut(), |ui| {
+            if let Some(code) = &room.code {
+                ui.label("Share this code with your friends:");
+                ui.heading(code);
+                return;
+            }
+            if !client.is_some_and(|client| client.is_connected()) {
+                ui.label("Connecting...");
+                return;
+            }
+            if ui.button("Create room").clicked() {
+                player_commands.send(PlayerCommand::CreateRoom);
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut *code_input)
+                        .hint_text("Join code")
+                        .char_limit(8),
+                );
+                if ui.button("Join").clicked() && !code_input.trim().is_empty() {
+                    player_commands.send(PlayerCommand::JoinRoom {
+                        code: code_input.trim().to_uppercase(),
+                    });
+                }
+            });
+            if let Some(error) = &room.error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+        });
+}
+
+/// Show the current [`MatchSettings`]. Only the lobby host can edit them; everyone else
+/// gets a read-only view of what the server replicated.
+fn match_settings_window(
+    mut egui_contexts: EguiContexts,
+    mut settings: ResMut<MatchSettings>,
+    lobby_host: Res<LobbyHost>,
+    client_id: Option<Res<CurrentClientId>>,
+    mut player_commands: EventWriter<PlayerCommand>,
+    mut unsent: Local<bool>,
+) {
+    let is_host = client_id.is_some_and(|id| lobby_host.0 == Some(id.0));
+    let mut draft = *settings;
+
+    egui::Window::new("Match Settings")
+        .resizable(false)
+        .show(egui_contexts.ctx_mut(), |ui| {
+            if !is_host {
+                ui.label("Only the host can change these settings.");
+            }
+            ui.add_enabled_ui(is_host, |ui| {
+                ui.add(
+                    egui::Slider::new(
+                        &mut draft.match_length_secs,
+                        MatchSettings::MATCH_LENGTH_SECS,
+                    )
+                    .text("Match length (s)"),
+                );
+                ui.add(
+                    egui::Slider::new(
+                        &mut draft.coin_spawn_interval_secs,
+                        MatchSettings::COIN_SPAWN_INTERVAL_SECS,
+                    )
+                    .text("Coin spawn interval (s)"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut draft.max_coins, MatchSettings::MAX_COINS)
+                        .text("Max coins"),
+                );
+                ui.add(
+                    egui::Slider::new(
+                        &mut draft.coin_hotspot_share,
+                        MatchSettings::COIN_HOTSPOT_SHARE,
+                    )
+                    .text("Coins in hotspots"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut draft.coin_rain_coins, MatchSettings::COIN_RAIN_COINS)
+                        .text("Coins per rain (0 = no rain)"),
+                );
+                ui.add_enabled(
+                    draft.coin_rain_coins > 0,
+                    egui::Slider::new(
+                        &mut draft.coin_rain_interval_secs,
+                        MatchSettings::COIN_RAIN_INTERVAL_SECS,
+                    )
+                    .text("Coin rain interval (s)"),
+                );
+                ui.add(
+                    egui::Slider::new(
+                        &mut draft.projectile_speed,
+                        MatchSettings::PROJECTILE_SPEED,
+                    )
+                    .text("Projectile speed"),
+                );
+                ui.add(
+                    egui::Slider::new(
+                        &mut draft.projectile_range,
+                        MatchSettings::PROJECTILE_RANGE,
+                    )
+                    .text("Projectile range"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut draft.growth_factor, MatchSettings::GROWTH_FACTOR)
+                        .text("Growth per coin"),
+                );
+                egui::ComboBox::from_label("Growth curve")
+                    .selected_text(draft.growth_curve.name())
+                    .show_ui(ui, |ui| {
+                        for curve in GrowthCurve::ALL {
+                            ui.selectable_value(&mut draft.growth_curve, curve, curve.name());
+                        }
+                    });
+                ui.add(
+                    egui::Slider::new(
+                        &mut draft.max_growth_scale,
+                        MatchSettings::MAX_GROWTH_SCALE,
+                    )
+                    .text("Max growth (x size)"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut draft.size_slowdown, MatchSettings::SIZE_SLOWDOWN)
+                        .text("Slowdown from size"),
+                );
+                ui.checkbox(&mut draft.self_hits, "Own shots can hit you");
+                ui.checkbox(&mut draft.duck_collisions, "Ducks push each other");
+                ui.add(
+                    egui::Slider::new(
+                        &mut draft.fire_cooldown_secs,
+                        MatchSettings::FIRE_COOLDOWN_SECS,
+                    )
+                    .text("Fire cooldown (s)"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut draft.magazine_size, MatchSettings::MAGAZINE_SIZE)
+                        .text("Magazine size (0 = unlimited)"),
+                );
+                ui.add_enabled(
+                    draft.magazine_size > 0,
+                    egui::Slider::new(&mut draft.reload_secs, MatchSettings::RELOAD_SECS)
+                        .text("Reload time (s)"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut draft.surface_patches, MatchSettings::SURFACE_PATCHES)
+                        .text("Mud and ice patches"),
+                );
+                ui.horizontal(|ui| {
+                    ui.label("Map seed");
+                    ui.add(egui::DragValue::new(&mut draft.map_seed));
+                    if ui.button("Reroll").clicked() {
+                        draft.map_seed = fastrand::u64(..);
+                    }
+                });
+            });
+        });
+
+    if is_host && draft != *settings {
+        // Apply locally right away so the widgets don't snap back while we wait for the
+        // server to replicate the validated settings.
+        *settings = draft;
+        *unsent = true;
+    }
+    // Send once the host lets go of a slider, not every step of the drag.
+    if is_host && *unsent && !egui_contexts.ctx_mut().is_using_pointer() {
+        player_commands.send(PlayerCommand::UpdateMatchSettings(*settings));
+        *unsent = false;
+    }
+}
+
+/// List the room's bots. The host can add and remove them, and pick how each one plays.
+fn bots_window(
+    mut egui_contexts: EguiContexts,
+    lobby: Res<ClientLobby>,
+    profiles: Res<BotProfiles>,
+    lobby_host: Res<LobbyHost>,
+    client_id: Option<Res<CurrentClientId>>,
+    mut player_commands: EventWriter<PlayerCommand>,
+) {
+    let is_host = client_id.is_some_and(|id| lobby_host.0 == Some(id.0));
+    let mut bots: Vec<_> = lobby.bots.iter().collect();
+    bots.sort_by_key(|(id, _)| **id);
+
+    egui::Window::new("Bots")
+        .resizable(false)
+        .show(egui_contexts.ctx_mut(), |ui| {
+            if bots.is_empty() {
+                ui.label("No bots in this room.");
+            }
+            ui.add_enabled_ui(is_host, |ui| {
+                for (id, config) in bots {
+                    let mut draft = config.clone();
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Bot {}", id));
+                        egui::ComboBox::from_id_salt(("difficulty", id))
+                            .selected_text(draft.difficulty.clone())
+                            .show_ui(ui, |ui| {
+                                for difficulty in &profiles.difficulties {
+                                    ui.selectable_value(
+                                        &mut draft.difficulty,
+                                        difficulty.name.clone(),
+                                        difficulty.name.as_str(),
+                                    );
+                                }
+                            });
+                        egui::ComboBox::from_id_salt(("personality", id))
+                            .selected_text(draft.personality.clone())
+                            .show_ui(ui, |ui| {
+                                for personality in &profiles.personalities {
+                                    ui.selectable_value(
+                                        &mut draft.personality,
+                                        personality.name.clone(),
+                                        personality.name.as_str(),
+                                    );
+                                }
+                            });
+                        if ui.button("Remove").clicked() {
+                            player_commands.send(PlayerCommand::RemoveBot { id: *id });
+                        }
+                    });
+                    if draft != *config {
+                        player_commands.send(PlayerCommand::ConfigureBot {
+                            id: *id,
+                            config: draft,
+                        });
+                    }
+                }
+                if ui.button("Add bot").clicked() {
+                    player_commands.send(PlayerCommand::AddBot(profiles.default.clone()));
+                }
+            });
+        });
+}
+
+/// How everyone did in the match that just ended, until it's closed or the next one starts.
+fn results_window(
+    mut commands: Commands,
+    mut egui_contexts: EguiContexts,
+    results: Res<MatchResults>,
+    lobby: Res<ClientLobby>,
+    client_id: Option<Res<CurrentClientId>>,
+) {
+    let is_me = |id: ClientId| client_id.as_ref().is_some_and(|client_id| client_id.0 == id);
+    let name = |id: ClientId| {
+        if is_me(id) {
+            "You".to_string()
+        } else if lobby.bots.contains_key(&id) {
+            format!("Bot {}", id)
+        } else {
+            format!("Player {}", id)
+        }
+    };
+    let best = results.0.first().map(|result| result.total);
+    let winners: Vec<_> = results
+        .0
+        .iter()
+        .filter(|result| Some(result.total) == best)
+        .collect();
+
+    egui::Window::new("Results")
+        .resizable(false)
+        .show(egui_contexts.ctx_mut(), |ui| {
+            ui.heading(match winners.as_slice() {
+                [] => "Nobody played.".to_string(),
+                [winner] if is_me(winner.id) => "You win!".to_string(),
+                [winner] => format!("{} wins!", name(winner.id)),
+                _ => "It's a tie!".to_string(),
+            });
+            egui::Grid::new("results").striped(true).show(ui, |ui| {
+                for heading in ["", "", "Carried", "Banked", "Total"] {
+                    ui.label(heading);
+                }
+                ui.end_row();
+                for (rank, result) in results.0.iter().enumerate() {
+                    ui.label(format!("{}.", rank + 1));
+                    ui.label(name(result.id));
+                    ui.label(result.carried.to_string());
+                    ui.label(result.banked.to_string());
+                    ui.label(result.total.to_string());
+                    ui.end_row();
+                }
+            });
+            if ui.button("Close").clicked() {
+                commands.remove_resource::<MatchResults>();
+            }
+        });
+}
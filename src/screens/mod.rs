@@ -1,7 +1,10 @@
 //! The game's main screen states and transitions between them.
 
+mod browse;
 mod credits;
 pub mod gameplay;
+#[cfg(not(target_family = "wasm"))]
+mod host;
 mod loading;
 pub mod lobby;
 mod splash;
@@ -14,6 +17,7 @@ pub(super) fn plugin(app: &mut App) {
     app.enable_state_scoped_entities::<Screen>();
 
     app.add_plugins((
+        browse::plugin,
         credits::plugin,
         lobby::plugin,
         gameplay::plugin,
@@ -21,6 +25,9 @@ pub(super) fn plugin(app: &mut App) {
         splash::plugin,
         title::plugin,
     ));
+
+    #[cfg(not(target_family = "wasm"))]
+    app.add_plugins(host::plugin);
 }
 
 /// The game's main screen states.
@@ -31,6 +38,8 @@ pub enum Screen {
     Loading,
     Title,
     Credits,
+    Browse,
+    Host,
     Lobby,
     Gameplay,
 }
@@ -2,7 +2,7 @@
 
 use bevy::prelude::*;
 
-use crate::{screens::Screen, theme::prelude::*};
+use crate::{demo::practice::start_practice, screens::Screen, theme::prelude::*};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Screen::Title), spawn_title_screen);
@@ -14,6 +14,10 @@ fn spawn_title_screen(mut commands: Commands) {
         .insert(StateScoped(Screen::Title))
         .with_children(|children| {
             children.button("Play").observe(enter_gameplay_screen);
+            #[cfg(not(target_family = "wasm"))]
+            children.button("Host Game").observe(enter_host_screen);
+            children.button("Servers").observe(enter_browse_screen);
+            children.button("Practice").observe(enter_practice);
             children.button("Credits").observe(enter_credits_screen);
 
             #[cfg(not(target_family = "wasm"))]
@@ -25,6 +29,24 @@ fn enter_gameplay_screen(_trigger: Trigger<OnPress>, mut next_screen: ResMut<Nex
     next_screen.set(Screen::Lobby);
 }
 
+#[cfg(not(target_family = "wasm"))]
+fn enter_host_screen(_trigger: Trigger<OnPress>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Host);
+}
+
+fn enter_browse_screen(_trigger: Trigger<OnPress>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Browse);
+}
+
+fn enter_practice(
+    _trigger: Trigger<OnPress>,
+    mut commands: Commands,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    commands.queue(start_practice);
+    next_screen.set(Screen::Lobby);
+}
+
 fn enter_credits_screen(_trigger: Trigger<OnPress>, mut next_screen: ResMut<NextState<Screen>>) {
     next_screen.set(Screen::Credits);
 }
@@ -0,0 +1,106 @@
+use chexy_butt_balloons::demo::{
+    client::{player_collider, PLAYER_BASE_COLLIDER_RADIUS},
+    growth::{Growth, PLAYER_DASH_DISTANCE, PLAYER_MOVE_SPEED},
+    lib::{GrowthCurve, MatchSettings},
+    physics::Shape,
+};
+
+fn settings(growth_curve: GrowthCurve) -> MatchSettings {
+    MatchSettings {
+        growth_curve,
+        ..MatchSettings::default()
+    }
+}
+
+#[test]
+fn a_duck_without_coins_is_full_size_and_full_speed() {
+    for curve in GrowthCurve::ALL {
+        let growth = Growth::from_score(0, &settings(curve));
+        assert_eq!(growth.scale, 1.0, "{:?}", curve);
+        assert_eq!(growth.collider_radius, PLAYER_BASE_COLLIDER_RADIUS);
+        assert_eq!(growth.max_speed, PLAYER_MOVE_SPEED);
+    }
+}
+
+#[test]
+fn linear_growth_adds_the_same_for_every_coin() {
+    let settings = settings(GrowthCurve::Linear);
+    let growth = Growth::from_score(5, &settings);
+    assert!((growth.scale - 1.5).abs() < 1e-5, "scale {}", growth.scale);
+    assert!((growth.collider_radius - PLAYER_BASE_COLLIDER_RADIUS * 1.5).abs() < 1e-5);
+}
+
+#[test]
+fn more_coins_means_bigger_and_slower() {
+    for curve in GrowthCurve::ALL {
+        let settings = settings(curve);
+        let mut previous = Growth::from_score(0, &settings);
+        for score in 1..=20 {
+            let growth = Growth::from_score(score, &settings);
+            assert!(growth.scale > previous.scale, "{:?} at {}", curve, score);
+            assert!(growth.collider_radius > previous.collider_radius);
+            assert!(growth.max_speed < previous.max_speed);
+            assert!(growth.acceleration < previous.acceleration);
+            assert!(growth.deceleration < previous.deceleration);
+            previous = growth;
+        }
+    }
+}
+
+#[test]
+fn bent_curves_grow_less_than_linear() {
+    let linear = Growth::from_score(16, &settings(GrowthCurve::Linear));
+    let square_root = Growth::from_score(16, &settings(GrowthCurve::SquareRoot));
+    let logarithmic = Growth::from_score(16, &settings(GrowthCurve::Logarithmic));
+    assert!(square_root.scale < linear.scale);
+    assert!(logarithmic.scale < square_root.scale);
+}
+
+#[test]
+fn growth_stops_at_the_cap() {
+    let settings = MatchSettings {
+        max_growth_scale: 2.0,
+        ..settings(GrowthCurve::Linear)
+    };
+    let growth = Growth::from_score(1000, &settings);
+    assert_eq!(growth.scale, 2.0);
+    assert_eq!(growth.collider_radius, PLAYER_BASE_COLLIDER_RADIUS * 2.0);
+}
+
+#[test]
+fn no_slowdown_keeps_big_ducks_fast() {
+    let settings = MatchSettings {
+        size_slowdown: 0.0,
+        ..settings(GrowthCurve::Linear)
+    };
+    assert_eq!(
+        Growth::from_score(15, &settings).max_speed,
+        PLAYER_MOVE_SPEED
+    );
+}
+
+#[test]
+fn negative_scores_never_shrink_a_duck() {
+    for curve in GrowthCurve::ALL {
+        assert_eq!(Growth::from_score(-10, &settings(curve)).scale, 1.0);
+    }
+}
+
+#[test]
+fn the_collider_matches_the_growth() {
+    let growth = Growth::from_score(8, &settings(GrowthCurve::SquareRoot));
+    match player_collider(growth).shape {
+        Shape::Circle(radius) => assert_eq!(radius, growth.collider_radius),
+        shape => panic!("ducks should be round, not {:?}", shape),
+    }
+}
+
+#[test]
+fn bigger_ducks_dash_shorter() {
+    let settings = settings(GrowthCurve::Linear);
+    assert_eq!(
+        Growth::from_score(0, &settings).dash_distance,
+        PLAYER_DASH_DISTANCE
+    );
+    assert!(Growth::from_score(10, &settings).dash_distance < PLAYER_DASH_DISTANCE);
+}
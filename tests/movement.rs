@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use chexy_butt_balloons::demo::movement::{MovementController, Surface};
+
+const TICK: f32 = 1. / 60.;
+
+fn controller(intent: Vec2) -> MovementController {
+    MovementController {
+        intent,
+        max_speed: 300.,
+        acceleration: 2400.,
+        deceleration: 3000.,
+    }
+}
+
+/// How many ticks it takes to go from `velocity` to `target`.
+fn ticks_until(
+    controller: &MovementController,
+    mut velocity: Vec2,
+    surface: Option<Surface>,
+    target: Vec2,
+) -> usize {
+    for tick in 1..10_000 {
+        velocity = controller.accelerate(velocity, surface, TICK);
+        if velocity.distance(target) < 1e-3 {
+            return tick;
+        }
+    }
+    panic!("never got from {} to {}", velocity, target);
+}
+
+#[test]
+fn speeds_up_gradually_and_stops_at_top_speed() {
+    let controller = controller(Vec2::X);
+    let velocity = controller.accelerate(Vec2::ZERO, None, TICK);
+    assert!((velocity.x - 2400. * TICK).abs() < 1e-3, "{}", velocity);
+
+    // 300 / 2400 = 0.125s, or 7.5 ticks.
+    assert_eq!(
+        ticks_until(&controller, Vec2::ZERO, None, Vec2::X * 300.),
+        8
+    );
+    let velocity = controller.accelerate(Vec2::X * 300., None, TICK);
+    assert_eq!(velocity, Vec2::X * 300.);
+}
+
+#[test]
+fn slows_down_to_a_stop_without_intent() {
+    let controller = controller(Vec2::ZERO);
+    // 300 / 3000 = 0.1s, or 6 ticks.
+    assert_eq!(
+        ticks_until(&controller, Vec2::X * 300., None, Vec2::ZERO),
+        6
+    );
+}
+
+#[test]
+fn ice_slides_further_than_plain_ground() {
+    let controller = controller(Vec2::ZERO);
+    let ground = ticks_until(&controller, Vec2::X * 300., None, Vec2::ZERO);
+    let ice = ticks_until(&controller, Vec2::X * 300., Some(Surface::Ice), Vec2::ZERO);
+    assert!(ice > ground * 5, "{} vs {}", ice, ground);
+}
+
+#[test]
+fn mud_caps_the_top_speed() {
+    let controller = controller(Vec2::Y);
+    let mut velocity = Vec2::ZERO;
+    for _ in 0..120 {
+        velocity = controller.accelerate(velocity, Some(Surface::Mud), TICK);
+    }
+    assert!((velocity.length() - 300. * Surface::Mud.speed()).abs() < 1e-3);
+}
+
+#[test]
+fn turning_around_takes_time() {
+    let controller = controller(Vec2::NEG_X);
+    let velocity = controller.accelerate(Vec2::X * 300., None, TICK);
+    assert!(velocity.x > 0., "{}", velocity);
+}
+
+#[test]
+fn surfaces_round_trip_through_their_map_object_id() {
+    for surface in Surface::ALL {
+        assert_eq!(
+            Surface::from_game_object_id(surface.game_object_id()),
+            Some(surface)
+        );
+    }
+    assert_eq!(Surface::from_game_object_id(0), None);
+}
@@ -0,0 +1,262 @@
+use bevy::prelude::*;
+use chexy_butt_balloons::demo::physics::{
+    check_collision, push_apart, sweep_collision, Collider, CollisionLayers,
+};
+
+fn collider(width: f32, height: f32) -> Collider {
+    Collider::aabb(
+        Vec2::new(width, height),
+        CollisionLayers::STATIC,
+        CollisionLayers::ALL,
+    )
+}
+
+fn circle(radius: f32) -> Collider {
+    Collider::circle(radius, CollisionLayers::PLAYER, CollisionLayers::ALL)
+}
+
+fn projectile() -> Collider {
+    collider(12., 18.)
+}
+
+#[test]
+fn hits_a_thin_wall_it_would_tunnel_through() {
+    let wall = collider(4., 200.);
+    let start = Vec2::new(-50., 0.);
+    // 500 units/s at 5 ticks per second.
+    let motion = Vec2::new(100., 0.);
+
+    // Checking only where the projectile ends up misses the wall entirely.
+    assert!(!check_collision(
+        &(start + motion).extend(0.),
+        &projectile(),
+        &Vec3::ZERO,
+        &wall,
+    ));
+
+    let time = sweep_collision(start, motion, &projectile(), Vec2::ZERO, &wall)
+        .expect("the projectile should hit the wall");
+    // It touches the wall once its front edge (x + 6) reaches the wall's back edge (x = -2).
+    assert!((time - 0.42).abs() < 1e-5, "hit at {}", time);
+}
+
+#[test]
+fn hits_a_small_duck_at_very_high_speed() {
+    let duck = collider(14., 24.);
+    let start = Vec2::new(0., -1000.);
+    let motion = Vec2::new(0., 5000.);
+
+    let time = sweep_collision(start, motion, &projectile(), Vec2::ZERO, &duck)
+        .expect("the projectile should hit the duck");
+    assert!(
+        (time - (1000. - 21.) / 5000.).abs() < 1e-5,
+        "hit at {}",
+        time
+    );
+}
+
+#[test]
+fn hits_diagonally() {
+    let wall = collider(2., 2.);
+    let time = sweep_collision(
+        Vec2::new(-100., -100.),
+        Vec2::new(200., 200.),
+        &collider(2., 2.),
+        Vec2::ZERO,
+        &wall,
+    )
+    .expect("the box should hit the wall");
+    assert!((time - 0.49).abs() < 1e-5, "hit at {}", time);
+}
+
+#[test]
+fn misses_a_wall_it_passes_beside() {
+    let wall = collider(4., 200.);
+    let start = Vec2::new(-50., 150.);
+    let motion = Vec2::new(100., 0.);
+    assert_eq!(
+        sweep_collision(start, motion, &projectile(), Vec2::ZERO, &wall),
+        None
+    );
+}
+
+#[test]
+fn misses_a_wall_it_stops_short_of() {
+    let wall = collider(4., 200.);
+    let start = Vec2::new(-100., 0.);
+    let motion = Vec2::new(50., 0.);
+    assert_eq!(
+        sweep_collision(start, motion, &projectile(), Vec2::ZERO, &wall),
+        None
+    );
+}
+
+#[test]
+fn misses_a_wall_behind_it() {
+    let wall = collider(4., 200.);
+    let start = Vec2::new(-50., 0.);
+    let motion = Vec2::new(-500., 0.);
+    assert_eq!(
+        sweep_collision(start, motion, &projectile(), Vec2::ZERO, &wall),
+        None
+    );
+}
+
+#[test]
+fn grazing_an_edge_is_not_a_hit() {
+    let wall = collider(10., 10.);
+    // Slides along the top edge of the wall, touching it the whole way.
+    let start = Vec2::new(-50., 5. + 9.);
+    let motion = Vec2::new(100., 0.);
+    assert_eq!(
+        sweep_collision(start, motion, &projectile(), Vec2::ZERO, &wall),
+        None
+    );
+}
+
+#[test]
+fn already_overlapping_hits_straight_away() {
+    let wall = collider(20., 20.);
+    assert_eq!(
+        sweep_collision(
+            Vec2::new(3., 0.),
+            Vec2::new(100., 0.),
+            &projectile(),
+            Vec2::ZERO,
+            &wall
+        ),
+        Some(0.0)
+    );
+}
+
+#[test]
+fn standing_still_only_hits_when_overlapping() {
+    let wall = collider(20., 20.);
+    assert_eq!(
+        sweep_collision(Vec2::ZERO, Vec2::ZERO, &projectile(), Vec2::ZERO, &wall),
+        Some(0.0)
+    );
+    assert_eq!(
+        sweep_collision(
+            Vec2::new(100., 0.),
+            Vec2::ZERO,
+            &projectile(),
+            Vec2::ZERO,
+            &wall
+        ),
+        None
+    );
+}
+
+#[test]
+fn circles_only_touch_within_their_radii() {
+    // Boxes around them would overlap at the corners, the circles don't.
+    assert!(!check_collision(
+        &Vec3::ZERO,
+        &circle(10.),
+        &Vec3::new(15., 15., 0.),
+        &circle(10.),
+    ));
+    assert!(check_collision(
+        &Vec3::ZERO,
+        &circle(10.),
+        &Vec3::new(15., 0., 0.),
+        &circle(10.),
+    ));
+}
+
+#[test]
+fn circle_misses_the_corner_of_a_box() {
+    let wall = collider(20., 20.);
+    assert!(!check_collision(
+        &Vec3::new(17., 17., 0.),
+        &circle(8.),
+        &Vec3::ZERO,
+        &wall,
+    ));
+    assert!(check_collision(
+        &Vec3::new(17., 0., 0.),
+        &circle(8.),
+        &Vec3::ZERO,
+        &wall,
+    ));
+}
+
+#[test]
+fn sweeps_circles_exactly() {
+    let time = sweep_collision(
+        Vec2::new(-100., 0.),
+        Vec2::new(200., 0.),
+        &circle(5.),
+        Vec2::ZERO,
+        &circle(5.),
+    )
+    .expect("the circles should meet");
+    assert!((time - 0.45).abs() < 1e-5, "hit at {}", time);
+
+    // Passes close enough for the boxes around them to touch, but not the circles.
+    assert_eq!(
+        sweep_collision(
+            Vec2::new(-100., 9.),
+            Vec2::new(200., 0.),
+            &circle(5.),
+            Vec2::ZERO,
+            &circle(5.),
+        ),
+        None
+    );
+}
+
+#[test]
+fn both_sides_have_to_want_the_collision() {
+    let duck = circle(7.);
+    let bullet = Collider::aabb(
+        Vec2::new(12., 18.),
+        CollisionLayers::PROJECTILE,
+        CollisionLayers::PLAYER | CollisionLayers::STATIC,
+    );
+    let pond = Collider::aabb(
+        Vec2::new(110., 80.),
+        CollisionLayers::STATIC,
+        CollisionLayers::PLAYER,
+    );
+    assert!(duck.interacts_with(&pond));
+    assert!(bullet.interacts_with(&duck));
+    assert!(!bullet.interacts_with(&pond));
+    assert!(!pond.interacts_with(&bullet));
+}
+
+#[test]
+fn equal_ducks_share_the_push() {
+    let (a, b) =
+        push_apart((Vec2::ZERO, 7., 1.), (Vec2::new(10., 0.), 7., 1.)).expect("the ducks overlap");
+    assert_eq!(a, Vec2::new(-2., 0.));
+    assert_eq!(b, Vec2::new(2., 0.));
+}
+
+#[test]
+fn heavier_ducks_get_pushed_less() {
+    let (small, big) =
+        push_apart((Vec2::ZERO, 7., 1.), (Vec2::new(0., 20.), 14., 3.)).expect("the ducks overlap");
+    assert!((small - Vec2::new(0., -0.75)).length() < 1e-5, "{}", small);
+    assert!((big - Vec2::new(0., 0.25)).length() < 1e-5, "{}", big);
+}
+
+#[test]
+fn pushing_is_the_same_either_way_round() {
+    let a = (Vec2::new(3., -2.), 9., 1.5);
+    let b = (Vec2::new(-4., 5.), 7., 2.5);
+    let (a_push, b_push) = push_apart(a, b).expect("the ducks overlap");
+    assert_eq!(push_apart(b, a), Some((b_push, a_push)));
+    // Afterwards they're just touching.
+    let distance = (a.0 + a_push).distance(b.0 + b_push);
+    assert!((distance - 16.).abs() < 1e-4, "{}", distance);
+}
+
+#[test]
+fn ducks_apart_are_not_pushed() {
+    assert_eq!(
+        push_apart((Vec2::ZERO, 7., 1.), (Vec2::new(14., 0.), 7., 1.)),
+        None
+    );
+}